@@ -0,0 +1,83 @@
+pub const SCALE_BITS: u32 = 12;
+pub const SCALE: u32 = 1 << SCALE_BITS;
+
+pub struct FreqTable {
+    pub freq: [u32; 256],
+    pub start: [u32; 256],
+}
+
+impl FreqTable {
+    pub fn compute(data: &[u8]) -> Self {
+        let mut counts = [0u64; 256];
+        data.iter().for_each(|byte| counts[*byte as usize] += 1);
+
+        let mut freq = [0u32; 256];
+        let total = data.len() as u64;
+        if total > 0 {
+            for s in 0..256 {
+                if counts[s] > 0 {
+                    freq[s] = std::cmp::max(1, (counts[s] * SCALE as u64 / total) as u32);
+                }
+            }
+            let mut sum: u32 = freq.iter().sum();
+            while sum != SCALE {
+                let max = (0..256).max_by_key(|&s| freq[s]).unwrap();
+                if sum > SCALE {
+                    let smaller = (0..256)
+                        .filter(|&s| freq[s] > 1)
+                        .max_by_key(|&s| freq[s])
+                        .unwrap();
+                    freq[smaller] -= 1;
+                    sum -= 1;
+                } else {
+                    freq[max] += 1;
+                    sum += 1;
+                }
+            }
+        }
+
+        return Self::from_freq(freq);
+    }
+
+    fn from_freq(freq: [u32; 256]) -> Self {
+        let mut start = [0u32; 256];
+        for s in 1..256 {
+            start[s] = start[s - 1] + freq[s - 1];
+        }
+        return Self { freq, start };
+    }
+
+    pub fn load(data: &[u8]) -> (Self, usize) {
+        let count = u16::from_le_bytes([data[0], data[1]]) as usize;
+        let mut freq = [0u32; 256];
+        for i in 0..count {
+            let entry = 2 + 3 * i;
+            freq[data[entry] as usize] =
+                u16::from_le_bytes([data[entry + 1], data[entry + 2]]) as u32;
+        }
+        return (Self::from_freq(freq), 2 + 3 * count);
+    }
+
+    pub fn dump(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        let symbols: Vec<usize> = (0..256).filter(|&s| self.freq[s] > 0).collect();
+
+        result.extend_from_slice(&(symbols.len() as u16).to_le_bytes());
+        for s in symbols {
+            result.push(s as u8);
+            result.extend_from_slice(&(self.freq[s] as u16).to_le_bytes());
+        }
+
+        return result;
+    }
+
+    pub fn slots(&self) -> Vec<u8> {
+        let mut slots = vec![0u8; SCALE as usize];
+        for s in 0..256 {
+            for slot in self.start[s]..self.start[s] + self.freq[s] {
+                slots[slot as usize] = s as u8;
+            }
+        }
+        return slots;
+    }
+}
@@ -0,0 +1,51 @@
+mod freq;
+pub mod rans;
+pub mod tans;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Coder {
+    Rans,
+    Tans,
+}
+
+impl Coder {
+    pub fn from_u8(x: u8) -> Coder {
+        if x == 0 {
+            Coder::Rans
+        } else {
+            Coder::Tans
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Coder::Rans => 0,
+            Coder::Tans => 1,
+        }
+    }
+}
+
+pub fn encode(data: &[u8], coder: Coder) -> Vec<u8> {
+    match coder {
+        Coder::Rans => rans::encode(data),
+        Coder::Tans => tans::encode(data),
+    }
+}
+
+pub fn decode(archive: &[u8], coder: Coder) -> Vec<u8> {
+    match coder {
+        Coder::Rans => rans::decode(archive),
+        Coder::Tans => tans::decode(archive),
+    }
+}
+
+pub fn compress(data: &Vec<u8>, coder: Coder) -> Vec<u8> {
+    let mut result = vec![coder.to_u8()];
+    result.append(&mut encode(data, coder));
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
+    return decode(&archive[1..], Coder::from_u8(archive[0]));
+}
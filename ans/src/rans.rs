@@ -0,0 +1,65 @@
+use crate::freq::{FreqTable, SCALE, SCALE_BITS};
+
+const RANS_L: u32 = 1 << 23;
+const STREAMS: usize = 4;
+
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let table = FreqTable::compute(data);
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    result.append(&mut table.dump());
+
+    let mut states = [RANS_L; STREAMS];
+    let mut stack = Vec::new();
+    for i in (0..data.len()).rev() {
+        let s = data[i] as usize;
+        let freq = table.freq[s];
+        let x = &mut states[i % STREAMS];
+
+        let x_max = ((RANS_L >> SCALE_BITS) << 8) * freq;
+        while *x >= x_max {
+            stack.push(*x as u8);
+            *x >>= 8;
+        }
+        *x = ((*x / freq) << SCALE_BITS) + (*x % freq) + table.start[s];
+    }
+    for x in states.iter().rev() {
+        stack.extend_from_slice(&x.to_le_bytes());
+    }
+    stack.reverse();
+    result.append(&mut stack);
+
+    return result;
+}
+
+pub fn decode(archive: &[u8]) -> Vec<u8> {
+    let length = u32::from_le_bytes([archive[0], archive[1], archive[2], archive[3]]) as usize;
+    let (table, size) = FreqTable::load(&archive[4..]);
+    let slots = table.slots();
+    let data = &archive[4 + size..];
+    let mut result = Vec::with_capacity(length);
+
+    let mut ptr = 0;
+    let mut states = [0u32; STREAMS];
+    for x in states.iter_mut() {
+        for _ in 0..4 {
+            *x = (*x << 8) | data[ptr] as u32;
+            ptr += 1;
+        }
+    }
+
+    for i in 0..length {
+        let x = &mut states[i % STREAMS];
+        let s = slots[(*x & (SCALE - 1)) as usize];
+        result.push(s);
+
+        let s = s as usize;
+        *x = table.freq[s] * (*x >> SCALE_BITS) + (*x & (SCALE - 1)) - table.start[s];
+        while *x < RANS_L {
+            *x = (*x << 8) | data[ptr] as u32;
+            ptr += 1;
+        }
+    }
+
+    return result;
+}
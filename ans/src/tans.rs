@@ -0,0 +1,170 @@
+use crate::freq::{FreqTable, SCALE, SCALE_BITS};
+
+struct DecodeEntry {
+    symbol: u8,
+    bits: u32,
+    base: u32,
+}
+
+fn spread(table: &FreqTable) -> Vec<u8> {
+    let mask = SCALE - 1;
+    let step = (SCALE >> 1) + (SCALE >> 3) + 3;
+    let mut spread = vec![0u8; SCALE as usize];
+    let mut pos = 0;
+    for s in 0..256 {
+        for _ in 0..table.freq[s] {
+            spread[pos as usize] = s as u8;
+            pos = (pos + step) & mask;
+        }
+    }
+    return spread;
+}
+
+fn log2(x: u32) -> u32 {
+    return 31 - x.leading_zeros();
+}
+
+fn encode_table(table: &FreqTable, spread: &Vec<u8>) -> Vec<u32> {
+    let mut next = table.start;
+    let mut states = vec![0u32; SCALE as usize];
+    for (slot, s) in spread.iter().enumerate() {
+        let s = *s as usize;
+        states[next[s] as usize] = SCALE + slot as u32;
+        next[s] += 1;
+    }
+    return states;
+}
+
+fn decode_table(table: &FreqTable, spread: &Vec<u8>) -> Vec<DecodeEntry> {
+    let mut next = table.freq;
+    let mut entries = Vec::with_capacity(SCALE as usize);
+    for s in spread {
+        let x = next[*s as usize];
+        next[*s as usize] += 1;
+        let bits = SCALE_BITS - log2(x);
+        entries.push(DecodeEntry {
+            symbol: *s,
+            bits,
+            base: (x << bits) - SCALE,
+        });
+    }
+    return entries;
+}
+
+struct BitWriter {
+    buffer: u64,
+    size: u32,
+    result: Vec<u8>,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            buffer: 0,
+            size: 0,
+            result: Vec::new(),
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.size;
+        self.size += count;
+        while self.size >= 8 {
+            self.result.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.size -= 8;
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.size > 0 {
+            self.result.push(self.buffer as u8);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    buffer: u64,
+    size: u32,
+    ptr: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            buffer: 0,
+            size: 0,
+            ptr: 0,
+        }
+    }
+
+    fn read_bits(&mut self, count: u32) -> u32 {
+        while self.size < count {
+            let byte = self.data.get(self.ptr).copied().unwrap_or(0);
+            self.buffer |= (byte as u64) << self.size;
+            self.size += 8;
+            self.ptr += 1;
+        }
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.size -= count;
+        return value as u32;
+    }
+}
+
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let table = FreqTable::compute(data);
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    result.append(&mut table.dump());
+    if data.is_empty() {
+        return result;
+    }
+
+    let states = encode_table(&table, &spread(&table));
+    let mut chunks = Vec::with_capacity(data.len());
+    let mut x = SCALE;
+    for byte in data.iter().rev() {
+        let s = *byte as usize;
+        let freq = table.freq[s];
+        let mut bits = 0;
+        while (x >> bits) >= 2 * freq {
+            bits += 1;
+        }
+        chunks.push((x & ((1 << bits) - 1), bits));
+        x = states[(table.start[s] + (x >> bits) - freq) as usize];
+    }
+    result.extend_from_slice(&((x - SCALE) as u16).to_le_bytes());
+
+    let mut writer = BitWriter::new();
+    for (value, bits) in chunks.iter().rev() {
+        writer.write_bits(*value, *bits);
+    }
+    writer.finish();
+    result.append(&mut writer.result);
+
+    return result;
+}
+
+pub fn decode(archive: &[u8]) -> Vec<u8> {
+    let length = u32::from_le_bytes([archive[0], archive[1], archive[2], archive[3]]) as usize;
+    let (table, size) = FreqTable::load(&archive[4..]);
+    let mut result = Vec::with_capacity(length);
+    if length == 0 {
+        return result;
+    }
+
+    let entries = decode_table(&table, &spread(&table));
+    let data = &archive[4 + size..];
+    let mut x = u16::from_le_bytes([data[0], data[1]]) as u32;
+    let mut reader = BitReader::new(&data[2..]);
+    for _ in 0..length {
+        let entry = &entries[x as usize];
+        result.push(entry.symbol);
+        x = entry.base + reader.read_bits(entry.bits);
+    }
+
+    return result;
+}
@@ -0,0 +1,138 @@
+pub struct BitWriter {
+    pub result: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self {
+            result: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    pub fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.result.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    pub fn align(&mut self) {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.align();
+        return self.result;
+    }
+}
+
+impl Default for BitWriter {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    caret: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            caret: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    pub fn read(&mut self, bits: u32) -> u32 {
+        while self.count < bits {
+            assert!(self.caret < self.data.len(), "unexpected end of stream");
+            self.buffer |= (self.data[self.caret] as u64) << self.count;
+            self.caret += 1;
+            self.count += 8;
+        }
+        let value = (self.buffer & ((1 << bits) - 1)) as u32;
+        self.buffer >>= bits;
+        self.count -= bits;
+        return value;
+    }
+
+    pub fn align(&mut self) {
+        self.buffer >>= self.count % 8;
+        self.count -= self.count % 8;
+    }
+
+    pub fn read_bytes(&mut self, n: usize) -> &'a [u8] {
+        assert!(self.count % 8 == 0, "byte read from an unaligned stream");
+        self.caret -= (self.count / 8) as usize;
+        self.buffer = 0;
+        self.count = 0;
+        assert!(
+            self.caret + n <= self.data.len(),
+            "unexpected end of stream"
+        );
+        let bytes = &self.data[self.caret..self.caret + n];
+        self.caret += n;
+        return bytes;
+    }
+
+    pub fn position(&self) -> usize {
+        return self.caret - (self.count / 8) as usize;
+    }
+
+    pub fn remaining(&self) -> usize {
+        return (self.data.len() - self.caret) * 8 + self.count as usize;
+    }
+}
+
+pub struct MsbBitWriter {
+    pub result: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl MsbBitWriter {
+    pub fn new() -> Self {
+        Self {
+            result: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    pub fn write(&mut self, value: u32, bits: u32) {
+        self.buffer = (self.buffer << bits) | value as u64;
+        self.count += bits;
+        while self.count >= 8 {
+            self.result.push((self.buffer >> (self.count - 8)) as u8);
+            self.count -= 8;
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+        return self.result;
+    }
+}
+
+impl Default for MsbBitWriter {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
@@ -0,0 +1,425 @@
+use clap::ValueEnum;
+use rangecoder::{Decoder, Encoder, PROB_ONE};
+
+const TABLE_BITS: u32 = 22;
+const MATCH_BITS: u32 = 20;
+const MATCH_MIN: usize = 6;
+const MATCH_MAX: usize = 65535;
+const COUNT_LIMIT: usize = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Preset {
+    Fast,
+    Max,
+}
+
+impl Preset {
+    fn from_u8(x: u8) -> Preset {
+        if x == 0 {
+            Preset::Fast
+        } else {
+            Preset::Max
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Preset::Fast => 0,
+            Preset::Max => 1,
+        }
+    }
+
+    fn orders(&self) -> usize {
+        match self {
+            Preset::Fast => 3,
+            Preset::Max => 5,
+        }
+    }
+}
+
+struct Logistic {
+    stretch: Vec<i32>,
+    squash: Vec<i32>,
+}
+
+impl Logistic {
+    fn new() -> Self {
+        let squash: Vec<i32> = (-2047..=2047)
+            .map(|d| {
+                let p = 4096.0 / (1.0 + (-(d as f64) / 256.0).exp());
+                (p as i32).clamp(1, 4095)
+            })
+            .collect();
+
+        let mut stretch = vec![0; 4096];
+        let mut pi = 0;
+        for x in -2047..=2047 {
+            let v = squash[(x + 2047) as usize];
+            for p in pi..=v {
+                stretch[p as usize] = x;
+            }
+            pi = v + 1;
+        }
+        for p in pi..4096 {
+            stretch[p as usize] = 2047;
+        }
+
+        return Self { stretch, squash };
+    }
+
+    fn squash(&self, d: i32) -> i32 {
+        return self.squash[(d.clamp(-2047, 2047) + 2047) as usize];
+    }
+
+    fn stretch(&self, p: i32) -> i32 {
+        return self.stretch[p as usize];
+    }
+}
+
+struct StateMap {
+    probs: Vec<u32>,
+    counts: Vec<u8>,
+    rates: Vec<u32>,
+    index: usize,
+}
+
+impl StateMap {
+    fn new(size: usize) -> Self {
+        Self {
+            probs: vec![1 << 31; size],
+            counts: vec![0; size],
+            rates: (0..=COUNT_LIMIT)
+                .map(|n| (65536.0 / (n as f64 + 1.5)) as u32)
+                .collect(),
+            index: 0,
+        }
+    }
+
+    fn p(&mut self, index: usize) -> i32 {
+        self.index = index;
+        return (self.probs[index] >> 20) as i32;
+    }
+
+    fn update(&mut self, bit: u32) {
+        let p = self.probs[self.index] >> 16;
+        let n = self.counts[self.index] as usize;
+        let rate = self.rates[n];
+        if bit == 1 {
+            self.probs[self.index] += ((65535 - p) * rate) & 0xFFFFFF00;
+        } else {
+            self.probs[self.index] -= (p * rate) & 0xFFFFFF00;
+        }
+        if n < COUNT_LIMIT {
+            self.counts[self.index] += 1;
+        }
+    }
+}
+
+struct Mixer {
+    weights: Vec<i32>,
+    inputs: Vec<i32>,
+    size: usize,
+    set: usize,
+    pr: i32,
+}
+
+impl Mixer {
+    fn new(size: usize, sets: usize) -> Self {
+        Self {
+            weights: vec![(1 << 16) / size as i32; size * sets],
+            inputs: Vec::with_capacity(size),
+            size,
+            set: 0,
+            pr: 2048,
+        }
+    }
+
+    fn add(&mut self, x: i32) {
+        self.inputs.push(x);
+    }
+
+    fn mix(&mut self, logistic: &Logistic, set: usize) -> i32 {
+        self.set = set * self.size;
+        let mut dot: i64 = 0;
+        for (i, x) in self.inputs.iter().enumerate() {
+            dot += *x as i64 * self.weights[self.set + i] as i64;
+        }
+        self.pr = logistic.squash((dot >> 16) as i32);
+        return self.pr;
+    }
+
+    fn update(&mut self, bit: u32) {
+        let err = (((bit as i32) << 12) - self.pr) * 6;
+        for (i, x) in self.inputs.iter().enumerate() {
+            self.weights[self.set + i] += (*x * err + 0x8000) >> 16;
+        }
+        self.inputs.clear();
+    }
+}
+
+struct Apm {
+    table: Vec<u32>,
+    index: usize,
+}
+
+impl Apm {
+    fn new(contexts: usize, logistic: &Logistic) -> Self {
+        let mut table = vec![0; contexts * 24];
+        for (i, t) in table.iter_mut().enumerate() {
+            let d = ((i % 24) as i32 * 2 + 1) * 4096 / 48 - 2048;
+            *t = logistic.squash(d) as u32 * 16;
+        }
+        return Self { table, index: 0 };
+    }
+
+    fn pp(&mut self, logistic: &Logistic, pr: i32, context: usize) -> i32 {
+        let pr = (logistic.stretch(pr) + 2048) * 23;
+        let weight = (pr & 0xFFF) as u32;
+        let context = context * 24 + (pr >> 12) as usize;
+        self.index = context + (weight >> 11) as usize;
+        let p = (self.table[context] * (4096 - weight) + self.table[context + 1] * weight) >> 16;
+        return p as i32;
+    }
+
+    fn update(&mut self, bit: u32) {
+        let rate = 7;
+        let g = (bit << 16) + (bit << rate) - bit - bit;
+        let t = &mut self.table[self.index];
+        *t = (*t as i32 + ((g as i32 - *t as i32) >> rate)) as u32;
+    }
+}
+
+struct MatchModel {
+    positions: Vec<u32>,
+    ptr: usize,
+    len: usize,
+    expected: u32,
+    map: StateMap,
+}
+
+impl MatchModel {
+    fn new() -> Self {
+        Self {
+            positions: vec![0; 1 << MATCH_BITS],
+            ptr: 0,
+            len: 0,
+            expected: 0,
+            map: StateMap::new(64 * 2),
+        }
+    }
+
+    fn update_byte(&mut self, history: &Vec<u8>) {
+        let pos = history.len();
+        if self.len > 0 && history[self.ptr] == history[pos - 1] {
+            self.len = std::cmp::min(self.len + 1, MATCH_MAX);
+            self.ptr += 1;
+        } else {
+            self.len = 0;
+        }
+        if pos < MATCH_MIN {
+            return;
+        }
+
+        let mut hash: u32 = 0;
+        for byte in &history[pos - MATCH_MIN..] {
+            hash = (hash ^ *byte as u32).wrapping_mul(0x2F0F3A35);
+        }
+        let hash = (hash >> (32 - MATCH_BITS)) as usize;
+        if self.len == 0 {
+            let candidate = self.positions[hash] as usize;
+            if candidate > 0 {
+                while self.len < MATCH_MAX
+                    && self.len < candidate
+                    && history[candidate - self.len - 1] == history[pos - self.len - 1]
+                {
+                    self.len += 1;
+                }
+                if self.len > 0 {
+                    self.ptr = candidate;
+                }
+            }
+        }
+        self.positions[hash] = pos as u32;
+    }
+
+    fn predict(&mut self, history: &Vec<u8>, logistic: &Logistic, bits: u32, c0: u32) -> i32 {
+        if self.len > 0 {
+            let byte = history[self.ptr] as u32 | 0x100;
+            if (byte >> (8 - bits)) != c0 {
+                self.len = 0;
+            }
+        }
+        if self.len == 0 {
+            return 0;
+        }
+        self.expected = (history[self.ptr] as u32 >> (7 - bits)) & 1;
+        let length = std::cmp::min(self.len, 63);
+        let p = self.map.p(length * 2 + self.expected as usize);
+        return logistic.stretch(p);
+    }
+
+    fn update(&mut self, bit: u32) {
+        if self.len > 0 {
+            self.map.update(bit);
+        }
+    }
+}
+
+fn hash(x: u32, y: u32) -> u32 {
+    let h = x.wrapping_mul(0x9E3779B1) ^ y.wrapping_mul(0x2F0F3A35);
+    return h ^ (h >> 15);
+}
+
+struct Predictor {
+    logistic: Logistic,
+    preset: Preset,
+    maps: Vec<StateMap>,
+    hashes: Vec<u32>,
+    matcher: MatchModel,
+    mixer: Mixer,
+    apm1: Apm,
+    apm2: Apm,
+    history: Vec<u8>,
+    c0: u32,
+    c4: u32,
+    word: u32,
+    bits: u32,
+    pr: i32,
+}
+
+impl Predictor {
+    fn new(preset: Preset) -> Self {
+        let logistic = Logistic::new();
+        let models = preset.orders() + if preset == Preset::Max { 1 } else { 0 };
+        let apm1 = Apm::new(256, &logistic);
+        let apm2 = Apm::new(1 << 16, &logistic);
+        let mut predictor = Self {
+            logistic,
+            preset,
+            maps: (0..models)
+                .map(|_| StateMap::new(1 << TABLE_BITS))
+                .collect(),
+            hashes: vec![0; models],
+            matcher: MatchModel::new(),
+            mixer: Mixer::new(models + 2, 256),
+            apm1,
+            apm2,
+            history: Vec::new(),
+            c0: 1,
+            c4: 0,
+            word: 0,
+            bits: 0,
+            pr: 2048,
+        };
+        predictor.predict();
+        return predictor;
+    }
+
+    fn update_contexts(&mut self) {
+        let byte = self.c4 & 0xFF;
+        let orders = self.preset.orders();
+        for n in 0..orders {
+            let mask = if n == 4 {
+                0xFFFFFFFF
+            } else {
+                (1u32 << (8 * n)) - 1
+            };
+            self.hashes[n] = hash(self.c4 & mask, n as u32);
+        }
+        if self.preset == Preset::Max {
+            if (byte as u8).is_ascii_alphabetic() {
+                self.word =
+                    (self.word ^ (byte as u8).to_ascii_lowercase() as u32).wrapping_mul(0x3D4D51CB);
+            } else {
+                self.word = 0;
+            }
+            self.hashes[orders] = hash(self.word, 0x100 + (self.c4 & 0xFF));
+        }
+        self.matcher.update_byte(&self.history);
+    }
+
+    fn predict(&mut self) {
+        for n in 0..self.maps.len() {
+            let index = hash(self.hashes[n], self.c0) >> (32 - TABLE_BITS);
+            let p = self.maps[n].p(index as usize);
+            self.mixer.add(self.logistic.stretch(p));
+        }
+        let m = self
+            .matcher
+            .predict(&self.history, &self.logistic, self.bits, self.c0);
+        self.mixer.add(m);
+        self.mixer.add(256);
+
+        let pr = self.mixer.mix(&self.logistic, self.c0 as usize);
+        let p1 = self.apm1.pp(&self.logistic, pr, self.c0 as usize);
+        let context = (self.c0 | ((self.c4 & 0xFF) << 8)) as usize;
+        let p2 = self.apm2.pp(&self.logistic, pr, context);
+        self.pr = ((pr + p1 + 2 * p2 + 2) >> 2).clamp(1, 4095);
+    }
+
+    fn update(&mut self, bit: u32) {
+        for map in &mut self.maps {
+            map.update(bit);
+        }
+        self.matcher.update(bit);
+        self.mixer.update(bit);
+        self.apm1.update(bit);
+        self.apm2.update(bit);
+
+        self.c0 = (self.c0 << 1) | bit;
+        self.bits += 1;
+        if self.bits == 8 {
+            let byte = (self.c0 & 0xFF) as u8;
+            self.history.push(byte);
+            self.c4 = (self.c4 << 8) | byte as u32;
+            self.c0 = 1;
+            self.bits = 0;
+            self.update_contexts();
+        }
+        self.predict();
+    }
+
+    fn p0(&self) -> u32 {
+        return PROB_ONE - self.pr as u32;
+    }
+}
+
+pub fn compress(data: &Vec<u8>, preset: Preset) -> Vec<u8> {
+    let mut result = Vec::new();
+    result.push(preset.to_u8());
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+    let mut predictor = Predictor::new(preset);
+    let mut encoder = Encoder::new();
+    for byte in data {
+        for i in (0..8).rev() {
+            let bit = (*byte as u32 >> i) & 1;
+            encoder.encode_with(predictor.p0(), bit);
+            predictor.update(bit);
+        }
+    }
+    encoder.finish();
+    result.append(&mut encoder.result);
+
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
+    let mut result = Vec::new();
+    let preset = Preset::from_u8(archive[0]);
+    let length = u32::from_le_bytes([archive[1], archive[2], archive[3], archive[4]]) as usize;
+
+    let mut predictor = Predictor::new(preset);
+    let mut decoder = Decoder::new(&archive[5..]);
+    for _ in 0..length {
+        let mut byte = 0;
+        for _ in 0..8 {
+            let bit = decoder.decode_with(predictor.p0());
+            predictor.update(bit);
+            byte = (byte << 1) | bit;
+        }
+        result.push(byte as u8);
+    }
+
+    return result;
+}
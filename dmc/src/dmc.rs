@@ -0,0 +1,143 @@
+use rangecoder::{Decoder, Encoder, PROB_ONE};
+
+#[derive(Clone, Copy)]
+pub struct Params {
+    pub min_edge: u32,
+    pub min_rest: u32,
+    pub max_states: u32,
+}
+
+impl Params {
+    fn load(data: &Vec<u8>) -> Self {
+        let read = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        return Self {
+            min_edge: read(0),
+            min_rest: read(4),
+            max_states: read(8),
+        };
+    }
+
+    fn dump(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        result.extend_from_slice(&self.min_edge.to_le_bytes());
+        result.extend_from_slice(&self.min_rest.to_le_bytes());
+        result.extend_from_slice(&self.max_states.to_le_bytes());
+        return result;
+    }
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    next: [u32; 2],
+    count: [f32; 2],
+}
+
+struct Model {
+    params: Params,
+    states: Vec<State>,
+    current: usize,
+}
+
+impl Model {
+    fn new(params: Params) -> Self {
+        let mut model = Self {
+            params,
+            states: Vec::new(),
+            current: 0,
+        };
+        model.reset();
+        return model;
+    }
+
+    fn reset(&mut self) {
+        self.states.clear();
+        for node in 1..256u32 {
+            let mut next = [0; 2];
+            for bit in 0..2 {
+                let child = node * 2 + bit;
+                next[bit as usize] = if child < 256 { child - 1 } else { 0 };
+            }
+            self.states.push(State {
+                next,
+                count: [0.2, 0.2],
+            });
+        }
+        self.current = 0;
+    }
+
+    fn p0(&self) -> u32 {
+        let count = self.states[self.current].count;
+        let p0 = count[0] / (count[0] + count[1]);
+        return (p0 * PROB_ONE as f32) as u32;
+    }
+
+    fn update(&mut self, bit: u32, byte_end: bool) {
+        let b = bit as usize;
+        let edge = self.states[self.current].count[b];
+        let mut target = self.states[self.current].next[b] as usize;
+        let total = self.states[target].count[0] + self.states[target].count[1];
+
+        if edge >= self.params.min_edge as f32
+            && total - edge >= self.params.min_rest as f32
+            && (self.states.len() as u32) < self.params.max_states
+        {
+            let ratio = edge / total;
+            let original = &mut self.states[target];
+            let clone = State {
+                next: original.next,
+                count: [original.count[0] * ratio, original.count[1] * ratio],
+            };
+            original.count[0] -= clone.count[0];
+            original.count[1] -= clone.count[1];
+            self.states.push(clone);
+            target = self.states.len() - 1;
+            self.states[self.current].next[b] = target as u32;
+        }
+
+        self.states[self.current].count[b] += 1.0;
+        self.current = target;
+
+        if byte_end && self.states.len() as u32 >= self.params.max_states {
+            self.reset();
+        }
+    }
+}
+
+pub fn compress(data: &Vec<u8>, params: Params) -> Vec<u8> {
+    let mut result = params.dump();
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+    let mut model = Model::new(params);
+    let mut encoder = Encoder::new();
+    for byte in data {
+        for i in (0..8).rev() {
+            let bit = (*byte as u32 >> i) & 1;
+            encoder.encode_with(model.p0(), bit);
+            model.update(bit, i == 0);
+        }
+    }
+    encoder.finish();
+    result.append(&mut encoder.result);
+
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
+    let mut result = Vec::new();
+    let params = Params::load(archive);
+    let length = u32::from_le_bytes([archive[12], archive[13], archive[14], archive[15]]) as usize;
+
+    let mut model = Model::new(params);
+    let mut decoder = Decoder::new(&archive[16..]);
+    for _ in 0..length {
+        let mut byte = 0;
+        for i in (0..8).rev() {
+            let bit = decoder.decode_with(model.p0());
+            model.update(bit, i == 0);
+            byte = (byte << 1) | bit;
+        }
+        result.push(byte as u8);
+    }
+
+    return result;
+}
@@ -0,0 +1,78 @@
+mod dmc;
+use clap::Parser;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+struct Cli {
+    #[arg(short)]
+    input_file: PathBuf,
+
+    #[arg(short)]
+    output_file: PathBuf,
+
+    #[arg(long, default_value_t = true)]
+    compress: bool,
+
+    #[arg(long, default_value_t = false)]
+    decompress: bool,
+
+    #[arg(long, default_value_t = 2)]
+    min_edge: u32,
+
+    #[arg(long, default_value_t = 2)]
+    min_rest: u32,
+
+    #[arg(long, default_value_t = 1 << 22)]
+    max_states: u32,
+}
+
+fn run_decompressor(cli: &Cli) -> Result<(), Error> {
+    let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
+    let mut archive = Vec::new();
+    input_f.read_to_end(&mut archive)?;
+    let data = dmc::decompress(&archive);
+
+    let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
+    output_f.write_all(&data)?;
+
+    return Ok(());
+}
+
+fn run_compressor(cli: &Cli) -> Result<(), Error> {
+    let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
+    let mut data = Vec::new();
+    input_f.read_to_end(&mut data)?;
+    let params = dmc::Params {
+        min_edge: cli.min_edge,
+        min_rest: cli.min_rest,
+        max_states: cli.max_states,
+    };
+    let archive = dmc::compress(&data, params);
+
+    let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
+    output_f.write_all(&archive)?;
+
+    return Ok(());
+}
+
+fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+
+    let result = if cli.decompress {
+        run_decompressor(&cli)
+    } else {
+        run_compressor(&cli)
+    };
+
+    if let Err(error) = result {
+        match error.kind() {
+            ErrorKind::NotFound => println!("Указанный файл не найден"),
+            ErrorKind::AlreadyExists => println!("Указанный файл уже существует"),
+            _ => println!("Произошла непредвиденная ошибка"),
+        };
+    }
+
+    Ok(())
+}
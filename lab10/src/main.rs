@@ -1,4 +1,5 @@
 mod rle;
+mod schemes;
 use clap::Parser;
 use std::fs::File;
 use std::io::{Error, ErrorKind, Read, Write};
@@ -21,6 +22,20 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     decompress: bool,
+
+    #[arg(long, value_enum, default_value_t = rle::Coder::Raw)]
+    coder: rle::Coder,
+
+    #[arg(long, value_enum, default_value_t = schemes::Scheme::Signed)]
+    scheme: schemes::Scheme,
+
+    #[arg(long, value_enum, default_value_t = scan::Scan::Rows)]
+    scan: scan::Scan,
+
+    // TIFF files are stored row by row in PackBits, so the archive options
+    // do not apply.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["coder", "scheme", "scan"])]
+    tiff: bool,
 }
 
 fn run_decompressor(cli: &Cli) -> Result<(), Error> {
@@ -45,6 +60,11 @@ fn run_decompressor(cli: &Cli) -> Result<(), Error> {
 }
 
 fn run_compressor(cli: &Cli) -> Result<(), Error> {
+    let params = rle::Params {
+        coder: cli.coder,
+        scheme: cli.scheme,
+        scan: cli.scan,
+    };
     let input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut decoder = tiff::decoder::Decoder::new(&input_f).unwrap();
     let img_coded = decoder.read_image();
@@ -52,8 +72,10 @@ fn run_compressor(cli: &Cli) -> Result<(), Error> {
     match img_coded {
         Ok(DecodingResult::U8(data)) => {
             let archive = match decoder.colortype() {
-                Ok(ColorType::RGB(_)) => rle::compress_rgb(&data, dim),
-                Ok(ColorType::Gray(_)) => rle::compress_gray(&data, dim),
+                Ok(ColorType::RGB(_)) if cli.tiff => rle::compress_tiff(&data, dim, 3),
+                Ok(ColorType::Gray(_)) if cli.tiff => rle::compress_tiff(&data, dim, 1),
+                Ok(ColorType::RGB(_)) => rle::compress_rgb(&data, dim, &params),
+                Ok(ColorType::Gray(_)) => rle::compress_gray(&data, dim, &params),
                 _ => panic!("unsupported colortype"),
             };
             let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
@@ -1,54 +1,139 @@
-fn dump_unique(encoded: &mut Vec<i8>, unique: &mut Vec<u8>) {
-    let mut ptr = 0;
-    while ptr < unique.len() {
-        let l = std::cmp::min(127, unique.len() - ptr);
-        encoded.push(-(l as i8));
-        for i in 0..l {
-            encoded.push(unique[ptr + i] as i8);
+use crate::schemes::{self, Run, Scheme};
+use clap::ValueEnum;
+use rangecoder::{Decoder, Encoder, Model};
+use scan::{self, Scan};
+
+const TIFF_PACKBITS: u16 = 32773;
+const MAX_ADAPTIVE_RUN: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Coder {
+    Raw,
+    Adaptive,
+}
+
+impl Coder {
+    fn from_u8(x: u8) -> Coder {
+        if x == 0 {
+            Coder::Raw
+        } else {
+            Coder::Adaptive
         }
-        ptr += l;
     }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Coder::Raw => 0,
+            Coder::Adaptive => 1,
+        }
+    }
+}
+
+pub struct Params {
+    pub coder: Coder,
+    pub scheme: Scheme,
+    pub scan: Scan,
 }
 
-fn dump_repeat(encoded: &mut Vec<i8>, byte: u8, repeat: usize) {
-    let mut ptr = 0;
-    while ptr < repeat {
-        let l = std::cmp::min(127, repeat - ptr);
-        encoded.push(l as i8);
-        encoded.push(byte as i8);
-        ptr += l;
+impl Params {
+    fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let runs = schemes::split(data, self.scheme);
+        return match self.coder {
+            Coder::Raw => schemes::dump(&runs, self.scheme),
+            Coder::Adaptive => adaptive_encode(&runs),
+        };
+    }
+
+    fn decode(&self, data: &[u8], pixels: usize) -> Vec<u8> {
+        let runs = match self.coder {
+            Coder::Raw => schemes::load(data, self.scheme, pixels),
+            Coder::Adaptive => adaptive_decode(data, pixels),
+        };
+        return schemes::expand(&runs);
     }
 }
 
-fn rle_encode(data: &Vec<u8>) -> Vec<i8> {
-    let mut encoded = Vec::new();
+struct AdaptiveModel {
+    flags: Model,
+    lengths: Model,
+    bytes: Model,
+    last_flag: usize,
+    last_byte: usize,
+}
 
-    let mut last = data[0];
-    let mut count = 1;
-    let mut unique = Vec::new();
+impl AdaptiveModel {
+    fn new() -> Self {
+        Self {
+            flags: Model::new(2),
+            lengths: Model::new(2 * 128),
+            bytes: Model::new(2 * 256),
+            last_flag: 0,
+            last_byte: 0,
+        }
+    }
+}
 
-    let mut ptr = 1;
-    while ptr < data.len() {
-        let byte = data[ptr];
-        if byte == last {
-            count += 1;
-        } else {
-            if count == 1 {
-                unique.push(last);
+// Models the runs rather than a byte layout, so it works for every scheme.
+// Lengths are 7-bit, so longer runs go out in pieces of at most 128 bytes.
+fn adaptive_encode(runs: &[Run]) -> Vec<u8> {
+    let mut model = AdaptiveModel::new();
+    let mut encoder = Encoder::new();
+
+    for run in runs {
+        let flag = run.literal as u32;
+        let mut done = 0;
+        while done < run.count {
+            let count = std::cmp::min(run.count - done, MAX_ADAPTIVE_RUN);
+            encoder.encode_bit(model.flags.get(model.last_flag), flag);
+            encoder.encode_tree(&mut model.lengths, flag as usize * 128, 7, count as u32 - 1);
+
+            let bytes = if run.literal {
+                &run.bytes[done..done + count]
             } else {
-                dump_unique(&mut encoded, &mut unique);
-                unique.clear();
-                dump_repeat(&mut encoded, last, count);
+                &run.bytes[..]
+            };
+            for &byte in bytes {
+                let base = (model.last_byte >> 7) * 256;
+                encoder.encode_tree(&mut model.bytes, base, 8, byte as u32);
+                model.last_byte = byte as usize;
             }
-            last = byte;
-            count = 1;
+            model.last_flag = flag as usize;
+            done += count;
+        }
+    }
+    encoder.finish();
+
+    return encoder.result;
+}
+
+fn adaptive_decode(data: &[u8], pixels: usize) -> Vec<Run> {
+    let mut model = AdaptiveModel::new();
+    let mut decoder = Decoder::new(data);
+    let mut decoded = Vec::new();
+
+    let mut total = 0;
+    while total < pixels {
+        let flag = decoder.decode_bit(model.flags.get(model.last_flag));
+        let count = decoder.decode_tree(&mut model.lengths, flag as usize * 128, 7) as usize + 1;
+
+        let bytes = if flag == 1 { count } else { 1 };
+        let mut run = Run {
+            literal: flag == 1,
+            count,
+            bytes: Vec::with_capacity(bytes),
+        };
+        for _ in 0..bytes {
+            let base = (model.last_byte >> 7) * 256;
+            let byte = decoder.decode_tree(&mut model.bytes, base, 8);
+            run.bytes.push(byte as u8);
+            model.last_byte = byte as usize;
         }
-        ptr += 1;
+        decoded.push(run);
+        total += count;
+        model.last_flag = flag as usize;
     }
-    dump_unique(&mut encoded, &mut unique);
-    dump_repeat(&mut encoded, last, count);
 
-    return encoded;
+    return decoded;
 }
 
 fn split_data(data: &Vec<u8>) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
@@ -83,73 +168,69 @@ fn read_u32(data: &Vec<u8>, offset: usize) -> u32 {
     return unsafe { std::mem::transmute(val_bytes) };
 }
 
-pub fn compress_rgb(data: &Vec<u8>, dim: (u32, u32)) -> Vec<u8> {
-    let mut result = Vec::new();
-    let (r, g, b) = split_data(data);
-    let r_enc = rle_encode(&r);
-    let g_enc = rle_encode(&g);
-    let b_enc = rle_encode(&b);
-
-    result.push(1);
-    dump_u32(&mut result, dim.0);
-    dump_u32(&mut result, dim.1);
-
-    dump_u32(&mut result, r_enc.len() as u32);
-    for elem in r_enc {
-        result.push(elem as u8);
-    }
+fn dump_channel(result: &mut Vec<u8>, archive: Vec<u8>) {
+    dump_u32(result, archive.len() as u32);
+    result.extend_from_slice(&archive);
+}
 
-    dump_u32(&mut result, g_enc.len() as u32);
-    for elem in g_enc {
-        result.push(elem as u8);
-    }
+fn dump_header(result: &mut Vec<u8>, kind: u8, dim: (u32, u32), params: &Params, scan: Scan) {
+    result.push(kind);
+    dump_u32(result, dim.0);
+    dump_u32(result, dim.1);
+    result.push(params.coder.to_u8());
+    result.push(params.scheme.to_u8());
+    result.push(scan.to_u8());
+}
 
-    dump_u32(&mut result, b_enc.len() as u32);
-    for elem in b_enc {
-        result.push(elem as u8);
-    }
+pub fn compress_rgb(data: &Vec<u8>, dim: (u32, u32), params: &Params) -> Vec<u8> {
+    return scan::pick(params.scan, |scan| {
+        let mut result = Vec::new();
+        let (r, g, b) = split_data(&scan::apply(data, dim, 3, scan));
 
-    return result;
+        dump_header(&mut result, 1, dim, params, scan);
+        dump_channel(&mut result, params.encode(&r));
+        dump_channel(&mut result, params.encode(&g));
+        dump_channel(&mut result, params.encode(&b));
+
+        return result;
+    });
 }
 
-pub fn compress_gray(data: &Vec<u8>, dim: (u32, u32)) -> Vec<u8> {
-    let mut result = Vec::new();
-    let enc = rle_encode(data);
+pub fn compress_gray(data: &Vec<u8>, dim: (u32, u32), params: &Params) -> Vec<u8> {
+    return scan::pick(params.scan, |scan| {
+        let mut result = Vec::new();
 
-    result.push(0);
-    dump_u32(&mut result, dim.0);
-    dump_u32(&mut result, dim.1);
+        dump_header(&mut result, 0, dim, params, scan);
+        dump_channel(&mut result, params.encode(&scan::apply(data, dim, 1, scan)));
 
-    dump_u32(&mut result, enc.len() as u32);
-    for elem in enc {
-        result.push(elem as u8);
-    }
+        return result;
+    });
+}
 
-    return result;
+pub fn compress_tiff(data: &Vec<u8>, dim: (u32, u32), samples: u32) -> Vec<u8> {
+    let row = dim.0 as usize * samples as usize;
+    let strip_size = tiff_writer::rows_per_strip(dim, samples) as usize * row;
+    let strips: Vec<Vec<u8>> = data
+        .chunks(std::cmp::max(strip_size, 1))
+        .map(|strip| {
+            strip
+                .chunks(std::cmp::max(row, 1))
+                .flat_map(|row| {
+                    schemes::dump(&schemes::split(row, Scheme::Packbits), Scheme::Packbits)
+                })
+                .collect()
+        })
+        .collect();
+    return tiff_writer::write(dim, samples, TIFF_PACKBITS, false, &strips);
 }
 
-fn rle_decode(data: &Vec<i8>) -> Vec<u8> {
-    let mut result = Vec::new();
+fn load_channel(data: &Vec<u8>, shift: &mut usize, params: &Params, pixels: usize) -> Vec<u8> {
+    let size = read_u32(data, *shift) as usize;
+    *shift += 4;
+    let channel = &data[*shift..*shift + size];
+    *shift += size;
 
-    let mut ptr = 0;
-    while ptr < data.len() {
-        let repeat = data[ptr];
-        ptr += 1;
-        if repeat < 0 {
-            for i in 0..(repeat as i32).abs() {
-                result.push(data[ptr + i as usize] as u8);
-            }
-            ptr += repeat.abs() as usize;
-        } else {
-            let byte = data[ptr];
-            ptr += 1;
-            for _ in 0..repeat {
-                result.push(byte as u8);
-            }
-        }
-    }
-
-    return result;
+    return params.decode(channel, pixels);
 }
 
 pub fn decompress(data: &Vec<u8>) -> (Vec<u8>, (u32, u32)) {
@@ -157,42 +238,21 @@ pub fn decompress(data: &Vec<u8>) -> (Vec<u8>, (u32, u32)) {
     let is_gray = data[0] == 0;
     let width = read_u32(&data, 1);
     let height = read_u32(&data, 5);
+    let params = Params {
+        coder: Coder::from_u8(data[9]),
+        scheme: Scheme::from_u8(data[10]),
+        scan: Scan::from_u8(data[11]),
+    };
+    let pixels = width as usize * height as usize;
 
+    let mut shift = 12;
     if is_gray {
-        let mut archive = Vec::new();
-        for i in &data[13..] {
-            archive.push(*i as i8);
-        }
-        let mut decoded = rle_decode(&archive);
+        let mut decoded = load_channel(data, &mut shift, &params, pixels);
         result.append(&mut decoded);
     } else {
-        let mut shift = 9;
-        let rsize = read_u32(&data, shift) as usize;
-        shift += 4;
-        let mut r_archive = Vec::new();
-        for i in &data[shift..shift + rsize] {
-            r_archive.push(*i as i8);
-        }
-        shift += rsize;
-
-        let gsize = read_u32(&data, shift) as usize;
-        shift += 4;
-        let mut g_archive = Vec::new();
-        for i in &data[shift..shift + gsize] {
-            g_archive.push(*i as i8);
-        }
-        shift += gsize;
-
-        let bsize = read_u32(&data, shift) as usize;
-        shift += 4;
-        let mut b_archive = Vec::new();
-        for i in &data[shift..shift + bsize] {
-            b_archive.push(*i as i8);
-        }
-
-        let r_decode = rle_decode(&r_archive);
-        let g_decode = rle_decode(&g_archive);
-        let b_decode = rle_decode(&b_archive);
+        let r_decode = load_channel(data, &mut shift, &params, pixels);
+        let g_decode = load_channel(data, &mut shift, &params, pixels);
+        let b_decode = load_channel(data, &mut shift, &params, pixels);
 
         for i in 0..r_decode.len() {
             result.push(r_decode[i]);
@@ -201,5 +261,7 @@ pub fn decompress(data: &Vec<u8>) -> (Vec<u8>, (u32, u32)) {
         }
     }
 
+    let samples = if is_gray { 1 } else { 3 };
+    let result = scan::undo(&result, (width, height), samples, params.scan);
     return (result, (width, height));
 }
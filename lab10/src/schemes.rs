@@ -0,0 +1,247 @@
+use bits::{BitReader, BitWriter};
+use clap::ValueEnum;
+
+// Byte layouts for run-length data:
+//  signed:   n < 0 copies the next -n bytes, n > 0 repeats the next byte n
+//            times, both up to 127 (the lab's original scheme);
+//  packbits: Apple PackBits as used by TIFF, 0..=127 copies the next n + 1
+//            bytes, -127..=-1 repeats the next byte 1 - n times and -128 is
+//            skipped;
+//  bits:     a literal flag bit and an Elias gamma length per run, then the
+//            bytes, with no limit on run lengths.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Scheme {
+    Signed,
+    Packbits,
+    Bits,
+}
+
+impl Scheme {
+    pub fn from_u8(x: u8) -> Scheme {
+        match x {
+            0 => Scheme::Signed,
+            1 => Scheme::Packbits,
+            _ => Scheme::Bits,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Scheme::Signed => 0,
+            Scheme::Packbits => 1,
+            Scheme::Bits => 2,
+        }
+    }
+
+    // The longest run a single header can describe.
+    pub fn max_run(&self) -> usize {
+        match self {
+            Scheme::Signed => 127,
+            Scheme::Packbits => 128,
+            Scheme::Bits => usize::MAX,
+        }
+    }
+}
+
+// A literal run carries `count` bytes, a repeat carries the repeated byte.
+pub struct Run {
+    pub literal: bool,
+    pub count: usize,
+    pub bytes: Vec<u8>,
+}
+
+fn dump_literal(runs: &mut Vec<Run>, literal: &mut Vec<u8>, max_run: usize) {
+    for chunk in literal.chunks(max_run) {
+        runs.push(Run {
+            literal: true,
+            count: chunk.len(),
+            bytes: chunk.to_vec(),
+        });
+    }
+    literal.clear();
+}
+
+fn dump_repeat(runs: &mut Vec<Run>, byte: u8, repeat: usize, max_run: usize) {
+    let mut ptr = 0;
+    while ptr < repeat {
+        let l = std::cmp::min(max_run, repeat - ptr);
+        runs.push(Run {
+            literal: false,
+            count: l,
+            bytes: vec![byte],
+        });
+        ptr += l;
+    }
+}
+
+// The original splitting: every stretch of two or more equal bytes is a
+// repeat, and so is whatever stretch ends the data.
+fn signed_runs(data: &[u8]) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let (&first, rest) = match data.split_first() {
+        Some(split) => split,
+        None => return runs,
+    };
+    let max_run = Scheme::Signed.max_run();
+
+    let mut last = first;
+    let mut count = 1;
+    let mut unique = Vec::new();
+    for &byte in rest {
+        if byte == last {
+            count += 1;
+        } else {
+            if count == 1 {
+                unique.push(last);
+            } else {
+                dump_literal(&mut runs, &mut unique, max_run);
+                dump_repeat(&mut runs, last, count, max_run);
+            }
+            last = byte;
+            count = 1;
+        }
+    }
+    dump_literal(&mut runs, &mut unique, max_run);
+    dump_repeat(&mut runs, last, count, max_run);
+
+    return runs;
+}
+
+// A repeat header costs as much as `min_repeat - 1` literal bytes, so
+// shorter stretches stay in the surrounding literal run.
+fn split_runs(data: &[u8], min_repeat: usize, max_run: usize) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut literal = Vec::new();
+
+    let mut ptr = 0;
+    while ptr < data.len() {
+        let byte = data[ptr];
+        let mut run = 1;
+        while ptr + run < data.len() && data[ptr + run] == byte && run < max_run {
+            run += 1;
+        }
+        if run >= min_repeat {
+            dump_literal(&mut runs, &mut literal, max_run);
+            dump_repeat(&mut runs, byte, run, max_run);
+        } else {
+            literal.extend_from_slice(&data[ptr..ptr + run]);
+        }
+        ptr += run;
+    }
+    dump_literal(&mut runs, &mut literal, max_run);
+
+    return runs;
+}
+
+pub fn split(data: &[u8], scheme: Scheme) -> Vec<Run> {
+    match scheme {
+        Scheme::Signed => signed_runs(data),
+        Scheme::Packbits => split_runs(data, 3, scheme.max_run()),
+        Scheme::Bits => split_runs(data, 2, scheme.max_run()),
+    }
+}
+
+pub fn expand(runs: &[Run]) -> Vec<u8> {
+    let mut result = Vec::new();
+    for run in runs {
+        if run.literal {
+            result.extend_from_slice(&run.bytes);
+        } else {
+            result.resize(result.len() + run.count, run.bytes[0]);
+        }
+    }
+    return result;
+}
+
+fn write_gamma(writer: &mut BitWriter, value: usize) {
+    let bits = usize::BITS - value.leading_zeros();
+    writer.write(0, bits - 1);
+    for i in (0..bits).rev() {
+        writer.write((value >> i) as u32 & 1, 1);
+    }
+}
+
+fn read_gamma(reader: &mut BitReader) -> usize {
+    let mut zeros = 0;
+    while reader.read(1) == 0 {
+        zeros += 1;
+        assert!(zeros < usize::BITS, "broken run length");
+    }
+    let mut value = 1;
+    for _ in 0..zeros {
+        value = (value << 1) | reader.read(1) as usize;
+    }
+    return value;
+}
+
+pub fn dump(runs: &[Run], scheme: Scheme) -> Vec<u8> {
+    if scheme == Scheme::Bits {
+        let mut writer = BitWriter::new();
+        for run in runs {
+            writer.write(run.literal as u32, 1);
+            write_gamma(&mut writer, run.count);
+            for &byte in &run.bytes {
+                writer.write(byte as u32, 8);
+            }
+        }
+        return writer.finish();
+    }
+
+    let mut result = Vec::new();
+    for run in runs {
+        let header = match (scheme, run.literal) {
+            (Scheme::Signed, true) => -(run.count as i32),
+            (Scheme::Signed, false) => run.count as i32,
+            (_, true) => run.count as i32 - 1,
+            (_, false) => 1 - run.count as i32,
+        };
+        result.push(header as i8 as u8);
+        result.extend_from_slice(&run.bytes);
+    }
+    return result;
+}
+
+// `pixels` tells where the bit-oriented stream ends, the byte layouts end
+// with the data.
+pub fn load(data: &[u8], scheme: Scheme, pixels: usize) -> Vec<Run> {
+    let mut runs = Vec::new();
+
+    if scheme == Scheme::Bits {
+        let mut reader = BitReader::new(data);
+        let mut total = 0;
+        while total < pixels {
+            let literal = reader.read(1) == 1;
+            let count = read_gamma(&mut reader);
+            let bytes = if literal { count } else { 1 };
+            runs.push(Run {
+                literal,
+                count,
+                bytes: (0..bytes).map(|_| reader.read(8) as u8).collect(),
+            });
+            total += count;
+        }
+        return runs;
+    }
+
+    let mut ptr = 0;
+    while ptr < data.len() {
+        let header = data[ptr] as i8 as i32;
+        ptr += 1;
+        let (literal, count) = match scheme {
+            Scheme::Packbits if header == -128 => continue,
+            Scheme::Signed => (header < 0, header.unsigned_abs() as usize),
+            _ if header >= 0 => (true, header as usize + 1),
+            _ => (false, (1 - header) as usize),
+        };
+        let bytes = if literal { count } else { 1 };
+        assert!(ptr + bytes <= data.len(), "run-length data is cut short");
+        runs.push(Run {
+            literal,
+            count,
+            bytes: data[ptr..ptr + bytes].to_vec(),
+        });
+        ptr += bytes;
+    }
+
+    return runs;
+}
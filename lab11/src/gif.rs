@@ -0,0 +1,123 @@
+use crate::lzw::{lzw_encode, Flavor};
+use bits::BitWriter;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+const IMAGE_SEPARATOR: u8 = 0x2c;
+const TRAILER: u8 = 0x3b;
+const GLOBAL_PALETTE: u8 = 0x80;
+const MAX_SUB_BLOCK: usize = 255;
+
+fn dump_u16(result: &mut Vec<u8>, val: u32) {
+    assert!(
+        val <= u16::MAX as u32,
+        "GIF images are at most 65535 pixels wide"
+    );
+    result.extend_from_slice(&(val as u16).to_le_bytes());
+}
+
+// GIF stores palette indices, so the image must not have more than 256
+// distinct colours. Gray pixels become gray palette entries.
+fn palettize(data: &[u8], samples: usize) -> Option<(Vec<[u8; 3]>, Vec<u8>)> {
+    let mut palette = Vec::new();
+    let mut index: HashMap<&[u8], u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(data.len() / samples);
+
+    for pixel in data.chunks(samples) {
+        let i = match index.get(pixel) {
+            Some(&i) => i,
+            None => {
+                if palette.len() == 256 {
+                    return None;
+                }
+                palette.push([pixel[0], pixel[samples / 2], pixel[samples - 1]]);
+                index.insert(pixel, (palette.len() - 1) as u8);
+                (palette.len() - 1) as u8
+            }
+        };
+        indices.push(i);
+    }
+
+    return Some((palette, indices));
+}
+
+pub fn is_gif(data: &[u8]) -> bool {
+    return data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a");
+}
+
+// Reads the first image of a GIF file through the gif crate, so the files
+// `write` makes can be checked by a decoder other than ours. Palettes have
+// no gray type, so the pixels always come out as RGB.
+pub fn read(data: &[u8]) -> (Vec<u8>, (u32, u32)) {
+    let mut options = ::gif::DecodeOptions::new();
+    options.set_color_output(::gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(data).expect("broken GIF file");
+    let dim = (decoder.width() as u32, decoder.height() as u32);
+    let frame = decoder
+        .read_next_frame()
+        .expect("broken GIF file")
+        .expect("the GIF file has no image");
+    assert!(
+        (frame.left, frame.top) == (0, 0) && (frame.width as u32, frame.height as u32) == dim,
+        "the GIF image does not cover the whole screen"
+    );
+
+    let mut result = Vec::with_capacity(dim.0 as usize * dim.1 as usize * 3);
+    for pixel in frame.buffer.chunks(4) {
+        result.extend_from_slice(&pixel[..3]);
+    }
+    return (result, dim);
+}
+
+pub fn write(data: &Vec<u8>, dim: (u32, u32), samples: u32) -> Result<Vec<u8>, Error> {
+    let (palette, indices) = palettize(data, samples as usize).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "the image has more than 256 colours, GIF needs a palette image",
+        )
+    })?;
+    // The palette holds 2^depth entries, and LZW literals are at least
+    // two bits wide.
+    let colors = std::cmp::max(palette.len(), 2) as u32;
+    let depth = 32 - (colors - 1).leading_zeros();
+    let literal_bits = std::cmp::max(depth, 2);
+
+    let mut result = b"GIF87a".to_vec();
+    dump_u16(&mut result, dim.0);
+    dump_u16(&mut result, dim.1);
+    result.push(GLOBAL_PALETTE | ((depth - 1) << 4) as u8 | (depth - 1) as u8);
+    result.push(0);
+    result.push(0);
+    for i in 0..1 << depth {
+        result.extend_from_slice(&palette.get(i).copied().unwrap_or([0; 3]));
+    }
+
+    result.push(IMAGE_SEPARATOR);
+    dump_u16(&mut result, 0);
+    dump_u16(&mut result, 0);
+    dump_u16(&mut result, dim.0);
+    dump_u16(&mut result, dim.1);
+    result.push(0);
+
+    // Like giflib, clear the table one code before it is full.
+    let flavor = Flavor {
+        literal_bits,
+        max_bits: 12,
+        early_change: false,
+        limit: (1 << 12) - 1,
+        framed: true,
+    };
+    let mut writer = BitWriter::new();
+    lzw_encode(&indices, &[], &flavor, &mut |code, width| {
+        writer.write(code, width)
+    });
+    result.push(literal_bits as u8);
+    for block in writer.finish().chunks(MAX_SUB_BLOCK) {
+        result.push(block.len() as u8);
+        result.extend_from_slice(block);
+    }
+    result.push(0);
+    result.push(TRAILER);
+
+    return Ok(result);
+}
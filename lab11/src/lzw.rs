@@ -1,111 +1,316 @@
-const DICT_SIZE: usize = 4096;
+use crate::lz78::{lz78_compress, lz78_decompress};
+use bits::{BitReader, BitWriter, MsbBitWriter};
+use checksum::adler32;
+use clap::ValueEnum;
+use rangecoder::{Decoder, Encoder, Model};
+use scan::{self, Scan};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Coder {
+    Raw,
+    Adaptive,
+}
 
-pub fn lz_match(data: &Vec<u8>, pos1: usize, pos2: usize, length: usize) -> bool {
-    for i in 0..length {
-        if data[pos1 + i] != data[pos2 + i] {
-            return false;
+impl Coder {
+    fn from_u8(x: u8) -> Coder {
+        if x == 0 {
+            Coder::Raw
+        } else {
+            Coder::Adaptive
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Coder::Raw => 0,
+            Coder::Adaptive => 1,
         }
     }
-    return true;
 }
 
-fn lzw_encode(data: &Vec<u8>) -> Vec<(u32, u8)> {
-    let mut encoded = Vec::new();
-    let mut dict = Vec::new();
-    let mut count = 1;
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Method {
+    Lzw,
+    Lz78,
+}
 
-    let mut ptr = 0;
-    while ptr < data.len() {
-        let mut saved = None;
-        let mut l = 1;
-        while ptr + l < data.len() && l < 256 {
-            let mut found = false;
-            for i in (0..dict.len()).rev() {
-                let (pos1, l1, val) = dict[i];
-                if l != l1 {
-                    continue;
-                }
-                if lz_match(data, pos1, ptr, l) {
-                    found = true;
-                    saved = Some((pos1, l, val));
-                    l += 1;
-                    break;
-                }
-            }
-            if !found {
-                break;
-            }
-        }
-        if let Some((_, l, val)) = saved {
-            let t = (val, data[ptr + l]);
-            encoded.push(t);
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, l + 1, count));
-                count += 1;
-            }
-            ptr += l + 1;
+impl Method {
+    fn from_u8(x: u8) -> Method {
+        if x == 0 {
+            Method::Lzw
         } else {
-            encoded.push((0, data[ptr]));
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, 1, count));
-                count += 1;
-            }
-            ptr += 1;
+            Method::Lz78
         }
     }
 
-    return encoded;
+    fn to_u8(self) -> u8 {
+        match self {
+            Method::Lzw => 0,
+            Method::Lz78 => 1,
+        }
+    }
 }
 
-pub fn lzw_compress(data: &Vec<u8>) -> Vec<u8> {
-    let mut result = Vec::new();
-    let encoded = lzw_encode(data);
+// The preset dictionary primes every channel.
+pub struct Params {
+    pub coder: Coder,
+    pub method: Method,
+    pub max_bits: u32,
+    pub scan: Scan,
+    pub dict: Vec<u8>,
+}
 
-    for (value, byte) in &encoded {
-        let value_bytes: [u8; 4] = unsafe { std::mem::transmute(*value) };
-        result.push(value_bytes[0]);
-        result.push(value_bytes[1]);
-        result.push(*byte);
+impl Params {
+    fn encode(&self, data: &Vec<u8>) -> Vec<u8> {
+        match self.method {
+            Method::Lzw => lzw_compress(data, &self.dict, self.max_bits),
+            Method::Lz78 => lz78_compress(data, &self.dict),
+        }
     }
 
-    return result;
+    fn decode(&self, data: &Vec<u8>) -> Vec<u8> {
+        match self.method {
+            Method::Lzw => lzw_decompress(data, &self.dict, self.max_bits),
+            Method::Lz78 => lz78_decompress(data, &self.dict),
+        }
+    }
 }
 
-pub fn lzw_decompress(data: &Vec<u8>) -> Vec<u8> {
-    let mut decoded = Vec::new();
-    let mut dict = Vec::new();
-    let mut count = 1;
-    let mut ptr = 0;
-
-    let mut caret = 0;
-    while caret < data.len() {
-        let value_bytes: [u8; 4] = [data[caret + 0], data[caret + 1], 0, 0];
-        let value: u32 = unsafe { std::mem::transmute(value_bytes) };
-        let byte = data[caret + 2];
-        caret += 3;
-
-        if value == 0 {
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, 1, count));
-                count += 1;
+const MAX_LITERAL_BITS: u32 = 8;
+const TIFF_LZW: u16 = 5;
+
+fn code_width(next: u32, min_bits: u32, max_bits: u32) -> u32 {
+    let bits = 32 - next.leading_zeros();
+    return bits.clamp(min_bits, max_bits);
+}
+
+// Code stream conventions differ between containers: TIFF (and our own
+// format) widen codes one code early, GIF and .Z one code late. TIFF and GIF
+// clear the table a little before it runs out and bracket the stream with
+// CLEAR and END, while .Z starts straight with data and stops at the end of
+// file, so its first free code is 257.
+pub struct Flavor {
+    pub literal_bits: u32,
+    pub max_bits: u32,
+    pub early_change: bool,
+    pub limit: u32,
+    pub framed: bool,
+}
+
+impl Flavor {
+    pub fn clear_code(&self) -> u32 {
+        return 1 << self.literal_bits;
+    }
+
+    fn end_code(&self) -> u32 {
+        return self.clear_code() + 1;
+    }
+
+    fn first_code(&self) -> u32 {
+        return self.clear_code() + 1 + self.framed as u32;
+    }
+
+    // `next` is the encoder's next free code at the moment the code is sent.
+    fn width(&self, next: u32) -> u32 {
+        let next = next - !self.early_change as u32;
+        return code_width(next, self.literal_bits + 1, self.max_bits);
+    }
+}
+
+// The encoder's phrases: (prefix code, next byte) -> code.
+type Table = HashMap<(u32, u8), u32>;
+
+// Walks a preset dictionary the way the encoder would and returns the
+// phrases it adds, both as the encoder's lookup table and as the decoder's
+// (position, length) pairs into the dictionary. The phrase still open at the
+// end is dropped, and one code is left free so the stream itself decides
+// when the table fills up and gets cleared.
+fn prime(dict: &[u8], flavor: &Flavor) -> (Table, Vec<(usize, usize)>) {
+    let mut table = HashMap::new();
+    let mut phrases = Vec::new();
+
+    if let Some((&first, _)) = dict.split_first() {
+        let mut code = first as u32;
+        let mut start = 0;
+        for (i, &byte) in dict.iter().enumerate().skip(1) {
+            if let Some(&child) = table.get(&(code, byte)) {
+                code = child;
+                continue;
             }
-            decoded.push(byte);
-            ptr += 1;
+            let next = flavor.first_code() + phrases.len() as u32;
+            if next + 1 < flavor.limit {
+                table.insert((code, byte), next);
+                phrases.push((start, i - start + 1));
+            }
+            code = byte as u32;
+            start = i;
+        }
+    }
+
+    return (table, phrases);
+}
+
+// The decoder adds each phrase one code later than the encoder, so the
+// encoder sizes a code by its own next free code and the decoder by its
+// next free code plus one. A primed stream has no leading CLEAR, which
+// would throw the preset phrases away.
+pub fn lzw_encode(data: &[u8], dict: &[u8], flavor: &Flavor, emit: &mut impl FnMut(u32, u32)) {
+    let (mut table, phrases) = prime(dict, flavor);
+    let mut next = flavor.first_code() + phrases.len() as u32;
+
+    if flavor.framed && dict.is_empty() {
+        emit(flavor.clear_code(), flavor.width(next));
+    }
+    if let Some((&first, rest)) = data.split_first() {
+        let mut code = first as u32;
+        for &byte in rest {
+            if let Some(&child) = table.get(&(code, byte)) {
+                code = child;
+                continue;
+            }
+            emit(code, flavor.width(next));
+            table.insert((code, byte), next);
+            next += 1;
+            if next == flavor.limit {
+                emit(flavor.clear_code(), flavor.width(next));
+                table.clear();
+                next = flavor.first_code();
+            }
+            code = byte as u32;
+        }
+        emit(code, flavor.width(next));
+        next += 1;
+    }
+    if flavor.framed {
+        emit(flavor.end_code(), flavor.width(next));
+    }
+}
+
+// `read` gets the width of the next code and returns None once the stream
+// is over, which is how unframed streams end.
+pub fn lzw_decode(
+    flavor: &Flavor,
+    dict: &[u8],
+    read: &mut impl FnMut(u32) -> Option<u32>,
+) -> Vec<u8> {
+    let max_code = 1 << flavor.max_bits;
+    let mut decoded = dict.to_vec();
+    let (_, mut table) = prime(dict, flavor);
+    let mut previous: Option<(usize, usize)> = None;
+
+    loop {
+        let next = flavor.first_code() + table.len() as u32;
+        let code = match read(flavor.width(next + previous.is_some() as u32)) {
+            Some(code) => code,
+            None => break,
+        };
+        if code == flavor.clear_code() {
+            table.clear();
+            previous = None;
+            continue;
+        }
+        if flavor.framed && code == flavor.end_code() {
+            break;
+        }
+
+        let start = decoded.len();
+        let (pos, length) = if code < flavor.clear_code() {
+            decoded.push(code as u8);
+            (start, 1)
         } else {
-            let (pos1, l, _) = dict[value as usize - 1];
-            for i in 0..l {
-                decoded.push(decoded[pos1 + i]);
+            let phrase = match previous {
+                _ if code >= flavor.first_code() && code < next => {
+                    table[(code - flavor.first_code()) as usize]
+                }
+                // KwKwK: the phrase being defined is the previous one plus
+                // its own first byte.
+                Some((pos, length)) if code == next => (pos, length + 1),
+                _ => panic!("LZW code is not in the table"),
+            };
+            for i in 0..phrase.1 {
+                decoded.push(decoded[phrase.0 + i]);
             }
-            decoded.push(byte);
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, l + 1, count));
-                count += 1;
+            (start, phrase.1)
+        };
+
+        if let Some((pos, length)) = previous {
+            if next < max_code {
+                table.push((pos, length + 1));
             }
-            ptr += l + 1;
         }
+        previous = Some((pos, length));
     }
 
-    return decoded;
+    return decoded.split_off(dict.len());
+}
+
+fn private_flavor(max_bits: u32) -> Flavor {
+    return Flavor {
+        literal_bits: MAX_LITERAL_BITS,
+        max_bits,
+        early_change: true,
+        limit: 1 << max_bits,
+        framed: true,
+    };
+}
+
+pub fn lzw_compress(data: &Vec<u8>, dict: &[u8], max_bits: u32) -> Vec<u8> {
+    assert!(
+        (MAX_LITERAL_BITS + 1..=24).contains(&max_bits),
+        "code width must be between 9 and 24 bits"
+    );
+    let flavor = private_flavor(max_bits);
+    let mut writer = BitWriter::new();
+    lzw_encode(data, dict, &flavor, &mut |code, width| {
+        writer.write(code, width)
+    });
+    return writer.finish();
+}
+
+fn tiff_lzw_compress(strip: &[u8]) -> Vec<u8> {
+    let flavor = Flavor {
+        literal_bits: MAX_LITERAL_BITS,
+        max_bits: 12,
+        early_change: true,
+        limit: (1 << 12) - 2,
+        framed: true,
+    };
+    let mut writer = MsbBitWriter::new();
+    lzw_encode(strip, &[], &flavor, &mut |code, width| {
+        writer.write(code, width)
+    });
+    return writer.finish();
+}
+
+// Horizontal differencing (TIFF predictor 2) within each row, sample by
+// sample, so smooth gradients turn into runs of small values.
+fn predict(data: &[u8], width: usize, samples: usize) -> Vec<u8> {
+    let mut result = data.to_vec();
+    for row in result.chunks_mut(width * samples) {
+        for i in (samples..row.len()).rev() {
+            row[i] = row[i].wrapping_sub(row[i - samples]);
+        }
+    }
+    return result;
+}
+
+pub fn compress_tiff(data: &Vec<u8>, dim: (u32, u32), samples: u32) -> Vec<u8> {
+    let predicted = predict(data, dim.0 as usize, samples as usize);
+    let strip_size =
+        tiff_writer::rows_per_strip(dim, samples) as usize * dim.0 as usize * samples as usize;
+    let strips: Vec<Vec<u8>> = predicted
+        .chunks(std::cmp::max(strip_size, 1))
+        .map(tiff_lzw_compress)
+        .collect();
+    return tiff_writer::write(dim, samples, TIFF_LZW, true, &strips);
+}
+
+pub fn lzw_decompress(data: &Vec<u8>, dict: &[u8], max_bits: u32) -> Vec<u8> {
+    let mut reader = BitReader::new(data);
+    return lzw_decode(&private_flavor(max_bits), dict, &mut |width| {
+        Some(reader.read(width))
+    });
 }
 
 fn split_data(data: &Vec<u8>) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
@@ -140,80 +345,134 @@ fn read_u32(data: &Vec<u8>, offset: usize) -> u32 {
     return unsafe { std::mem::transmute(val_bytes) };
 }
 
-pub fn compress_rgb(data: &Vec<u8>, dim: (u32, u32)) -> Vec<u8> {
+fn adaptive_encode(data: &Vec<u8>) -> Vec<u8> {
     let mut result = Vec::new();
-    let (r, g, b) = split_data(data);
-    let r_enc = lzw_compress(&r);
-    let g_enc = lzw_compress(&g);
-    let b_enc = lzw_compress(&b);
+    let mut model = Model::new(3 * 256);
+    let mut encoder = Encoder::new();
 
-    result.push(1);
-    dump_u32(&mut result, dim.0);
-    dump_u32(&mut result, dim.1);
-
-    dump_u32(&mut result, r_enc.len() as u32);
-    for elem in r_enc {
-        result.push(elem as u8);
+    dump_u32(&mut result, data.len() as u32);
+    for (i, byte) in data.iter().enumerate() {
+        encoder.encode_tree(&mut model, (i % 3) * 256, 8, *byte as u32);
     }
+    encoder.finish();
+    result.append(&mut encoder.result);
 
-    dump_u32(&mut result, g_enc.len() as u32);
-    for elem in g_enc {
-        result.push(elem as u8);
-    }
+    return result;
+}
+
+fn adaptive_decode(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    let mut model = Model::new(3 * 256);
+    let length = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let mut decoder = Decoder::new(&data[4..]);
 
-    dump_u32(&mut result, b_enc.len() as u32);
-    for elem in b_enc {
-        result.push(elem as u8);
+    for i in 0..length {
+        decoded.push(decoder.decode_tree(&mut model, (i % 3) * 256, 8) as u8);
     }
 
-    return result;
+    return decoded;
 }
 
-pub fn compress_gray(data: &Vec<u8>, dim: (u32, u32)) -> Vec<u8> {
-    let mut result = Vec::new();
-    let enc = lzw_compress(data);
+fn dump_channel(result: &mut Vec<u8>, encoded: Vec<u8>, coder: Coder) {
+    let archive = match coder {
+        Coder::Raw => encoded,
+        Coder::Adaptive => adaptive_encode(&encoded),
+    };
+    dump_u32(result, archive.len() as u32);
+    result.extend_from_slice(&archive);
+}
+
+fn load_channel(data: &Vec<u8>, shift: &mut usize, params: &Params) -> Vec<u8> {
+    let size = read_u32(data, *shift) as usize;
+    *shift += 4;
+    let channel = &data[*shift..*shift + size];
+    *shift += size;
+
+    let archive = match params.coder {
+        Coder::Raw => channel.to_vec(),
+        Coder::Adaptive => adaptive_decode(channel),
+    };
+    return params.decode(&archive);
+}
 
-    result.push(0);
-    dump_u32(&mut result, dim.0);
-    dump_u32(&mut result, dim.1);
+fn dump_header(result: &mut Vec<u8>, kind: u8, dim: (u32, u32), params: &Params, scan: Scan) {
+    result.push(kind);
+    dump_u32(result, dim.0);
+    dump_u32(result, dim.1);
+    result.push(params.coder.to_u8());
+    result.push(params.method.to_u8());
+    result.push(params.max_bits as u8);
+    result.push(scan.to_u8());
+    let id = if params.dict.is_empty() {
+        0
+    } else {
+        adler32(&params.dict)
+    };
+    dump_u32(result, id);
+}
 
-    dump_u32(&mut result, enc.len() as u32);
-    for elem in enc {
-        result.push(elem as u8);
-    }
+pub fn compress_rgb(data: &Vec<u8>, dim: (u32, u32), params: &Params) -> Vec<u8> {
+    return scan::pick(params.scan, |scan| {
+        let mut result = Vec::new();
+        let (r, g, b) = split_data(&scan::apply(data, dim, 3, scan));
+        let r_enc = params.encode(&r);
+        let g_enc = params.encode(&g);
+        let b_enc = params.encode(&b);
+
+        dump_header(&mut result, 1, dim, params, scan);
+        dump_channel(&mut result, r_enc, params.coder);
+        dump_channel(&mut result, g_enc, params.coder);
+        dump_channel(&mut result, b_enc, params.coder);
+
+        return result;
+    });
+}
 
-    return result;
+pub fn compress_gray(data: &Vec<u8>, dim: (u32, u32), params: &Params) -> Vec<u8> {
+    return scan::pick(params.scan, |scan| {
+        let mut result = Vec::new();
+        let enc = params.encode(&scan::apply(data, dim, 1, scan));
+
+        dump_header(&mut result, 0, dim, params, scan);
+        dump_channel(&mut result, enc, params.coder);
+
+        return result;
+    });
 }
 
-pub fn decompress(data: &Vec<u8>) -> (Vec<u8>, (u32, u32)) {
+pub fn decompress(data: &Vec<u8>, dict: &Vec<u8>) -> (Vec<u8>, (u32, u32)) {
     let mut result = Vec::new();
     let is_gray = data[0] == 0;
     let width = read_u32(&data, 1);
     let height = read_u32(&data, 5);
+    let mut params = Params {
+        coder: Coder::from_u8(data[9]),
+        method: Method::from_u8(data[10]),
+        max_bits: data[11] as u32,
+        scan: Scan::from_u8(data[12]),
+        dict: Vec::new(),
+    };
+    let id = read_u32(data, 13);
+    if id != 0 {
+        assert!(
+            !dict.is_empty(),
+            "archive needs a preset dictionary, pass --dict"
+        );
+        assert!(
+            id == adler32(dict),
+            "preset dictionary does not match the archive"
+        );
+        params.dict = dict.clone();
+    }
 
+    let mut shift = 17;
     if is_gray {
-        let archive = Vec::from(&data[13..]);
-        let mut decoded = lzw_decompress(&archive);
+        let mut decoded = load_channel(data, &mut shift, &params);
         result.append(&mut decoded);
     } else {
-        let mut shift = 9;
-        let rsize = read_u32(&data, shift) as usize;
-        shift += 4;
-        let r_archive = Vec::from(&data[shift..shift + rsize]);
-        shift += rsize;
-
-        let gsize = read_u32(&data, shift) as usize;
-        shift += 4;
-        let g_archive = Vec::from(&data[shift..shift + gsize]);
-        shift += gsize;
-
-        let bsize = read_u32(&data, shift) as usize;
-        shift += 4;
-        let b_archive = Vec::from(&data[shift..shift + bsize]);
-
-        let r_decode = lzw_decompress(&r_archive);
-        let g_decode = lzw_decompress(&g_archive);
-        let b_decode = lzw_decompress(&b_archive);
+        let r_decode = load_channel(data, &mut shift, &params);
+        let g_decode = load_channel(data, &mut shift, &params);
+        let b_decode = load_channel(data, &mut shift, &params);
 
         for i in 0..r_decode.len() {
             result.push(r_decode[i]);
@@ -222,5 +481,7 @@ pub fn decompress(data: &Vec<u8>) -> (Vec<u8>, (u32, u32)) {
         }
     }
 
+    let samples = if is_gray { 1 } else { 3 };
+    let result = scan::undo(&result, (width, height), samples, params.scan);
     return (result, (width, height));
 }
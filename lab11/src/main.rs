@@ -1,7 +1,10 @@
+mod gif;
+mod lz78;
 mod lzw;
-use clap::Parser;
+mod unix;
+use clap::{CommandFactory, Parser};
 use std::fs::File;
-use std::io::{Error, ErrorKind, Read, Write};
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
 use std::path::PathBuf;
 use tiff;
 use tiff::decoder::DecodingResult;
@@ -21,13 +24,69 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     decompress: bool,
+
+    #[arg(long)]
+    dict: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = lzw::Coder::Raw)]
+    coder: lzw::Coder,
+
+    #[arg(long, value_enum, default_value_t = lzw::Method::Lzw)]
+    method: lzw::Method,
+
+    // 12 bits by default, 16 for .Z files like compress(1). Codes are wider
+    // than the 8-bit literals, and the private format allows up to 24 bits.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(9..=24))]
+    max_code_bits: Option<u32>,
+
+    #[arg(long, value_enum, default_value_t = scan::Scan::Rows)]
+    scan: scan::Scan,
+
+    // TIFF and GIF files are stored row by row with their own LZW flavour,
+    // and have no preset dictionary.
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["gif", "dot_z", "dict", "coder", "method", "max_code_bits", "scan"]
+    )]
+    tiff: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["dot_z", "dict", "coder", "method", "max_code_bits", "scan"]
+    )]
+    gif: bool,
+
+    // Compress the input as a plain file into the .Z format of compress(1).
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["dict", "coder", "method", "scan"]
+    )]
+    dot_z: bool,
+}
+
+fn read_dict(cli: &Cli) -> Result<Vec<u8>, Error> {
+    let mut dict = Vec::new();
+    if let Some(path) = &cli.dict {
+        let mut dict_f = File::open(path.to_str().unwrap())?;
+        dict_f.read_to_end(&mut dict)?;
+    }
+    return Ok(dict);
 }
 
 fn run_decompressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut archive = Vec::new();
     input_f.read_to_end(&mut archive)?;
-    let (data, dim) = lzw::decompress(&archive);
+    if unix::is_z(&archive) {
+        let data = unix::z_decompress(&archive);
+        let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
+        output_f.write_all(&data)?;
+        return Ok(());
+    }
+    let (data, dim) = lzw::decompress(&archive, &read_dict(cli)?);
 
     let output_f = File::create(cli.output_file.to_str().unwrap())?;
     let mut encoder = tiff::encoder::TiffEncoder::new(output_f).unwrap();
@@ -44,34 +103,95 @@ fn run_decompressor(cli: &Cli) -> Result<(), Error> {
     return Ok(());
 }
 
-fn run_compressor(cli: &Cli) -> Result<(), Error> {
-    let input_f = File::open(cli.input_file.to_str().unwrap())?;
-    let mut decoder = tiff::decoder::Decoder::new(&input_f).unwrap();
+fn run_z_compressor(cli: &Cli) -> Result<(), Error> {
+    let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
+    let mut data = Vec::new();
+    input_f.read_to_end(&mut data)?;
+    let archive = unix::z_compress(&data, cli.max_code_bits.unwrap_or(16));
+
+    let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
+    output_f.write_all(&archive)?;
+
+    return Ok(());
+}
+
+fn read_tiff(input: &[u8]) -> (Vec<u8>, (u32, u32), u32) {
+    let mut decoder = tiff::decoder::Decoder::new(Cursor::new(input)).unwrap();
     let img_coded = decoder.read_image();
     let dim = decoder.dimensions().unwrap();
+    let samples = match decoder.colortype() {
+        Ok(ColorType::RGB(_)) => 3,
+        Ok(ColorType::Gray(_)) => 1,
+        _ => panic!("unsupported colortype"),
+    };
     match img_coded {
-        Ok(DecodingResult::U8(data)) => {
-            let archive = match decoder.colortype() {
-                Ok(ColorType::RGB(_)) => lzw::compress_rgb(&data, dim),
-                Ok(ColorType::Gray(_)) => lzw::compress_gray(&data, dim),
-                _ => panic!("unsupported colortype"),
-            };
-            let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
-            output_f.write_all(&archive)?;
-        }
-        _ => {
-            panic!("something went wrong");
-        }
+        Ok(DecodingResult::U8(data)) => return (data, dim, samples),
+        _ => panic!("something went wrong"),
     }
+}
+
+fn run_compressor(cli: &Cli) -> Result<(), Error> {
+    let params = lzw::Params {
+        coder: cli.coder,
+        method: cli.method,
+        max_bits: cli.max_code_bits.unwrap_or(12),
+        scan: cli.scan,
+        dict: read_dict(cli)?,
+    };
+    let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
+    let mut input = Vec::new();
+    input_f.read_to_end(&mut input)?;
+    let (data, dim, samples) = if gif::is_gif(&input) {
+        let (data, dim) = gif::read(&input);
+        (data, dim, 3)
+    } else {
+        read_tiff(&input)
+    };
+
+    let archive = if cli.tiff {
+        lzw::compress_tiff(&data, dim, samples)
+    } else if cli.gif {
+        gif::write(&data, dim, samples)?
+    } else if samples == 3 {
+        lzw::compress_rgb(&data, dim, &params)
+    } else {
+        lzw::compress_gray(&data, dim, &params)
+    };
+    let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
+    output_f.write_all(&archive)?;
 
     return Ok(());
 }
 
+// Checks that depend on the values of several arguments, so clap cannot
+// express them as attributes.
+fn check_args(cli: &Cli) {
+    if cli.method == lzw::Method::Lz78 && cli.max_code_bits.is_some() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "lz78 has no code width limit, drop --max-code-bits",
+            )
+            .exit();
+    }
+    if cli.dot_z && cli.max_code_bits.unwrap_or(16) > 16 {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ValueValidation,
+                ".Z files allow codes of 9 to 16 bits, --max-code-bits is too large",
+            )
+            .exit();
+    }
+}
+
 fn main() -> std::io::Result<()> {
     let cli = Cli::parse();
+    check_args(&cli);
 
     let result = if cli.decompress {
         run_decompressor(&cli)
+    } else if cli.dot_z {
+        run_z_compressor(&cli)
     } else {
         run_compressor(&cli)
     };
@@ -80,6 +200,7 @@ fn main() -> std::io::Result<()> {
         match error.kind() {
             ErrorKind::NotFound => println!("Указанный файл не найден"),
             ErrorKind::AlreadyExists => println!("Указанный файл уже существует"),
+            ErrorKind::InvalidInput => println!("{}", error),
             _ => println!("Произошла непредвиденная ошибка"),
         };
     }
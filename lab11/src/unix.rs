@@ -0,0 +1,110 @@
+use crate::lzw::{lzw_decode, lzw_encode, Flavor};
+use bits::{BitReader, BitWriter};
+
+// The .Z format of compress(1): two magic bytes, a flags byte holding the
+// maximum code width and the block mode bit, then LSB-first LZW codes.
+const MAGIC: [u8; 2] = [0x1f, 0x9d];
+const BLOCK_MODE: u8 = 0x80;
+const BITS_MASK: u8 = 0x1f;
+
+pub fn is_z(data: &[u8]) -> bool {
+    return data.len() >= 3 && data[0..2] == MAGIC;
+}
+
+fn flavor(max_bits: u32) -> Flavor {
+    return Flavor {
+        literal_bits: 8,
+        max_bits,
+        early_change: false,
+        limit: 1 << max_bits,
+        framed: false,
+    };
+}
+
+// compress writes codes in groups of eight, and each time the width changes
+// or the table is cleared it flushes the whole group, so readers skip to the
+// end of the group before going on.
+struct Groups {
+    width: u32,
+    count: u32,
+    cleared: bool,
+}
+
+impl Groups {
+    fn new() -> Self {
+        Self {
+            width: 9,
+            count: 0,
+            cleared: false,
+        }
+    }
+
+    // Returns how many filler codes, and how wide, come before a code of
+    // `width` bits.
+    fn fillers(&mut self, width: u32) -> (u32, u32) {
+        let old_width = self.width;
+        let mut fillers = 0;
+        if width != self.width || self.cleared {
+            fillers = (8 - self.count % 8) % 8;
+            self.width = width;
+            self.count = 0;
+            self.cleared = false;
+        }
+        self.count += 1;
+        return (fillers, old_width);
+    }
+}
+
+pub fn z_compress(data: &Vec<u8>, max_bits: u32) -> Vec<u8> {
+    assert!(
+        (9..=16).contains(&max_bits),
+        ".Z code width must be between 9 and 16 bits"
+    );
+    let flavor = flavor(max_bits);
+    let mut writer = BitWriter::new();
+    let mut groups = Groups::new();
+    lzw_encode(data, &[], &flavor, &mut |code, width| {
+        let (fillers, filler_width) = groups.fillers(width);
+        for _ in 0..fillers {
+            writer.write(0, filler_width);
+        }
+        writer.write(code, width);
+        groups.cleared = code == flavor.clear_code();
+    });
+
+    let mut result = MAGIC.to_vec();
+    result.push(BLOCK_MODE | max_bits as u8);
+    result.append(&mut writer.finish());
+    return result;
+}
+
+pub fn z_decompress(data: &Vec<u8>) -> Vec<u8> {
+    assert!(is_z(data), "not a .Z file");
+    assert!(
+        data[2] & BLOCK_MODE != 0,
+        "only block mode .Z files are supported"
+    );
+    let max_bits = (data[2] & BITS_MASK) as u32;
+    assert!(
+        (9..=16).contains(&max_bits),
+        ".Z code width must be between 9 and 16 bits"
+    );
+    let flavor = flavor(max_bits);
+    let mut reader = BitReader::new(&data[3..]);
+    let mut groups = Groups::new();
+    return lzw_decode(&flavor, &[], &mut |width| {
+        let (fillers, filler_width) = groups.fillers(width);
+        for _ in 0..fillers {
+            if reader.remaining() < filler_width as usize {
+                return None;
+            }
+            reader.read(filler_width);
+        }
+        if reader.remaining() < width as usize {
+            return None;
+        }
+        let code = reader.read(width);
+        groups.cleared = code == flavor.clear_code();
+        return Some(code);
+    });
+}
@@ -0,0 +1,191 @@
+const EMPTY: usize = usize::MAX;
+
+fn buckets(s: &[usize], k: usize, end: bool) -> Vec<usize> {
+    let mut counts = vec![0; k];
+    s.iter().for_each(|&c| counts[c] += 1);
+    let mut sum = 0;
+    for count in counts.iter_mut() {
+        sum += *count;
+        *count = if end { sum } else { sum - *count };
+    }
+    return counts;
+}
+
+fn induce(s: &[usize], k: usize, types: &Vec<bool>, sa: &mut Vec<usize>) {
+    let n = s.len();
+    let mut heads = buckets(s, k, false);
+    for i in 0..n {
+        let j = sa[i];
+        if j != EMPTY && j > 0 && !types[j - 1] {
+            sa[heads[s[j - 1]]] = j - 1;
+            heads[s[j - 1]] += 1;
+        }
+    }
+
+    let mut tails = buckets(s, k, true);
+    for i in (0..n).rev() {
+        let j = sa[i];
+        if j != EMPTY && j > 0 && types[j - 1] {
+            tails[s[j - 1]] -= 1;
+            sa[tails[s[j - 1]]] = j - 1;
+        }
+    }
+}
+
+fn is_lms(types: &Vec<bool>, i: usize) -> bool {
+    return i > 0 && types[i] && !types[i - 1];
+}
+
+fn lms_equal(s: &[usize], types: &Vec<bool>, a: usize, b: usize) -> bool {
+    let n = s.len();
+    for d in 0.. {
+        if a + d == n || b + d == n {
+            return false;
+        }
+        if s[a + d] != s[b + d] || types[a + d] != types[b + d] {
+            return false;
+        }
+        if d > 0 && (is_lms(types, a + d) || is_lms(types, b + d)) {
+            return is_lms(types, a + d) && is_lms(types, b + d);
+        }
+    }
+    return false;
+}
+
+fn sa_is(s: &[usize], k: usize) -> Vec<usize> {
+    let n = s.len();
+    if n == 1 {
+        return vec![0];
+    }
+
+    let mut types = vec![false; n];
+    types[n - 1] = true;
+    for i in (0..n - 1).rev() {
+        types[i] = s[i] < s[i + 1] || (s[i] == s[i + 1] && types[i + 1]);
+    }
+    let lms: Vec<usize> = (1..n).filter(|&i| is_lms(&types, i)).collect();
+
+    let mut sa = vec![EMPTY; n];
+    let mut tails = buckets(s, k, true);
+    for &i in lms.iter().rev() {
+        tails[s[i]] -= 1;
+        sa[tails[s[i]]] = i;
+    }
+    induce(s, k, &types, &mut sa);
+
+    let sorted: Vec<usize> = sa
+        .iter()
+        .copied()
+        .filter(|&i| i != EMPTY && is_lms(&types, i))
+        .collect();
+    let mut names = vec![EMPTY; n];
+    let mut name = 0;
+    names[sorted[0]] = 0;
+    for w in sorted.windows(2) {
+        if !lms_equal(s, &types, w[0], w[1]) {
+            name += 1;
+        }
+        names[w[1]] = name;
+    }
+    let reduced: Vec<usize> = lms.iter().map(|&i| names[i]).collect();
+
+    let reduced_sa = if name + 1 < lms.len() {
+        sa_is(&reduced, name + 1)
+    } else {
+        let mut reduced_sa = vec![0; lms.len()];
+        for (i, &c) in reduced.iter().enumerate() {
+            reduced_sa[c] = i;
+        }
+        reduced_sa
+    };
+
+    sa.fill(EMPTY);
+    let mut tails = buckets(s, k, true);
+    for &i in reduced_sa.iter().rev() {
+        let j = lms[i];
+        tails[s[j]] -= 1;
+        sa[tails[s[j]]] = j;
+    }
+    induce(s, k, &types, &mut sa);
+
+    return sa;
+}
+
+pub fn suffix_array(data: &[u8]) -> Vec<usize> {
+    let mut s: Vec<usize> = data.iter().map(|&c| c as usize + 1).collect();
+    s.push(0);
+    return sa_is(&s, 257);
+}
+
+fn transform(block: &[u8]) -> (Vec<u8>, u32) {
+    let sa = suffix_array(block);
+    let mut last = Vec::with_capacity(block.len());
+    let mut primary = 0;
+    for (row, &i) in sa.iter().enumerate() {
+        if i == 0 {
+            primary = row as u32;
+        } else {
+            last.push(block[i - 1]);
+        }
+    }
+    return (last, primary);
+}
+
+fn inverse(last: &[u8], primary: u32) -> Vec<u8> {
+    let n = last.len();
+    let primary = primary as usize;
+    let row_symbol = |row: usize| -> usize {
+        if row < primary {
+            last[row] as usize
+        } else {
+            last[row - 1] as usize
+        }
+    };
+
+    let mut counts = [0usize; 256];
+    last.iter().for_each(|&c| counts[c as usize] += 1);
+    let mut starts = [0usize; 256];
+    let mut sum = 1;
+    for c in 0..256 {
+        starts[c] = sum;
+        sum += counts[c];
+    }
+
+    let mut lf = vec![0usize; n + 1];
+    let mut seen = [0usize; 256];
+    for row in 0..=n {
+        if row == primary {
+            continue;
+        }
+        let c = row_symbol(row);
+        lf[row] = starts[c] + seen[c];
+        seen[c] += 1;
+    }
+
+    let mut result = vec![0u8; n];
+    let mut row = 0;
+    for k in (0..n).rev() {
+        result[k] = row_symbol(row) as u8;
+        row = lf[row];
+    }
+    return result;
+}
+
+pub fn encode(data: &Vec<u8>, block_size: usize) -> (Vec<u8>, Vec<u32>) {
+    let mut result = Vec::with_capacity(data.len());
+    let mut primaries = Vec::new();
+    for block in data.chunks(block_size) {
+        let (mut last, primary) = transform(block);
+        result.append(&mut last);
+        primaries.push(primary);
+    }
+    return (result, primaries);
+}
+
+pub fn decode(data: &Vec<u8>, block_size: usize, primaries: &Vec<u32>) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    for (block, primary) in data.chunks(block_size).zip(primaries) {
+        result.append(&mut inverse(block, *primary));
+    }
+    return result;
+}
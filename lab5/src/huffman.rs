@@ -1,5 +1,5 @@
 use crate::priority_queue::PriorityQueue;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug)]
 enum Bit {
@@ -80,67 +80,73 @@ impl<T: Eq + Ord + Copy + std::hash::Hash> HuffmanTree<T> {
 
 #[derive(Debug)]
 struct Metadata {
-    tree: HuffmanTree<u8>,
-    alphabet: Vec<u8>,
-    code: HashMap<u8, Vec<Bit>>,
+    tree: Option<HuffmanTree<u16>>,
+    counts: Vec<(u16, u32)>,
+    code: HashMap<u16, Vec<Bit>>,
     remainder: u8,
 }
 
 impl Metadata {
-    fn compute(data: &Vec<u8>) -> Self {
-        let mut alphabet = HashSet::new();
-        data.iter().for_each(|v| {
-            alphabet.insert(*v);
-        });
-        let mut alphabet = Vec::from_iter(alphabet);
-        alphabet.sort();
-
+    fn build(counts: Vec<(u16, u32)>, remainder: u8) -> Self {
         let mut queue = PriorityQueue::new();
-        for value in 1..=alphabet.len() as u8 {
-            queue.insert(value as u32, value);
+        for (symbol, count) in &counts {
+            queue.insert(*count, *symbol);
         }
-        let tree = HuffmanTree::from_queue(queue);
-        let code = tree.get_code();
+        let tree = if counts.is_empty() {
+            None
+        } else {
+            Some(HuffmanTree::from_queue(queue))
+        };
+        let code = match &tree {
+            Some(tree) => tree.get_code(),
+            None => HashMap::new(),
+        };
 
         return Self {
             tree,
-            alphabet,
+            counts,
             code,
-            remainder: 0,
+            remainder,
         };
     }
 
-    fn load(data: &Vec<u8>) -> Self {
+    fn compute(data: &Vec<u16>) -> Self {
+        let mut counts = HashMap::new();
+        data.iter().for_each(|symbol| {
+            *counts.entry(*symbol).or_insert(0) += 1;
+        });
+        let mut counts: Vec<(u16, u32)> = counts.drain().collect();
+        counts.sort();
+
+        return Self::build(counts, 0);
+    }
+
+    fn load(data: &[u8]) -> Self {
         let remainder = data[0];
-        let dict_len = data[1] as usize + 1;
-        let mut alphabet = Vec::new();
+        let dict_len = u16::from_le_bytes([data[1], data[2]]) as usize;
+        let mut counts = Vec::new();
         for i in 0..dict_len {
-            alphabet.push(data[2 + i]);
+            let entry = &data[3 + 6 * i..3 + 6 * (i + 1)];
+            let symbol = u16::from_le_bytes([entry[0], entry[1]]);
+            let count = u32::from_le_bytes([entry[2], entry[3], entry[4], entry[5]]);
+            counts.push((symbol, count));
         }
-        alphabet.sort();
 
-        let mut queue = PriorityQueue::new();
-        for value in 1..=alphabet.len() as u8 {
-            queue.insert(value as u32, value);
-        }
-        let tree = HuffmanTree::from_queue(queue);
-        let code = tree.get_code();
+        return Self::build(counts, remainder);
+    }
 
-        return Self {
-            tree,
-            alphabet,
-            code,
-            remainder,
-        };
+    fn size(&self) -> usize {
+        return 3 + 6 * self.counts.len();
     }
 
     fn dump(&self) -> Vec<u8> {
         let mut result = Vec::new();
 
         result.push(self.remainder);
-        result.push((self.alphabet.len() - 1) as u8);
-        for ch in &self.alphabet {
-            result.push(*ch);
+        result.extend_from_slice(&(self.counts.len() as u16).to_le_bytes());
+        for (symbol, count) in &self.counts {
+            result.extend_from_slice(&symbol.to_le_bytes());
+            result.extend_from_slice(&count.to_le_bytes());
         }
 
         return result;
@@ -193,20 +199,18 @@ impl BitWriter {
     }
 }
 
-pub fn compress(data: &Vec<u8>) -> Vec<u8> {
+pub fn compress(data: &Vec<u16>) -> Vec<u8> {
     let mut result = Vec::new();
     let mut metadata = Metadata::compute(data);
 
-    let mut alphabet = metadata.alphabet.clone();
     let mut writer = BitWriter::new();
-    data.iter().for_each(|&byte| {
-        let pos = alphabet.iter().position(|&x| x == byte).unwrap();
-        let bits = metadata.code.get(&(pos as u8 + 1)).unwrap();
-        alphabet.remove(pos);
-        alphabet.push(byte);
+    data.iter().for_each(|symbol| {
+        let bits = metadata.code.get(symbol).unwrap();
         writer.write_bits(&bits);
     });
-    writer.finish();
+    if !data.is_empty() {
+        writer.finish();
+    }
     metadata.remainder = writer.remainder;
 
     let md_dump = metadata.dump();
@@ -257,15 +261,18 @@ impl<'a> BitReader<'a> {
     }
 }
 
-pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
+pub fn decompress(archive: &[u8]) -> Vec<u16> {
     let mut result = Vec::new();
     let metadata = Metadata::load(archive);
+    let tree = match &metadata.tree {
+        Some(tree) => tree,
+        None => return result,
+    };
 
-    let data = archive[2 + metadata.alphabet.len()..].to_vec();
+    let data = archive[metadata.size()..].to_vec();
     let mut reader = BitReader::new(&data, &metadata);
 
-    let mut alphabet = metadata.alphabet.clone();
-    let mut state = &metadata.tree;
+    let mut state = tree;
     while let Some(bit) = reader.read_bit() {
         if let HuffmanTree::Node(left, right) = state {
             match bit {
@@ -274,11 +281,9 @@ pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
             }
         }
 
-        if let HuffmanTree::Leaf(pos) = state {
-            let byte = alphabet.remove((*pos - 1) as usize);
-            alphabet.push(byte);
-            result.push(byte);
-            state = &metadata.tree;
+        if let HuffmanTree::Leaf(symbol) = state {
+            result.push(*symbol);
+            state = tree;
         }
     }
 
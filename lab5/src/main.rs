@@ -1,6 +1,9 @@
+mod bwt;
 mod huffman;
+mod mtf;
 mod priority_queue;
-use clap::Parser;
+mod zrle;
+use clap::{Parser, ValueEnum};
 use std::fs::File;
 use std::io::{Error, ErrorKind, Read, Write};
 use std::path::PathBuf;
@@ -18,13 +21,133 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     decompress: bool,
+
+    #[arg(long, value_enum, default_value_t = Coder::Huffman)]
+    coder: Coder,
+
+    #[arg(long, value_enum, default_value_t = mtf::Variant::Mtf)]
+    mtf: mtf::Variant,
+
+    #[arg(long, default_value_t = 1)]
+    step: u8,
+
+    #[arg(long, default_value_t = false)]
+    bwt: bool,
+
+    // 0 is the header's "no BWT" marker, so blocks hold at least one byte.
+    #[arg(long, default_value_t = 900000, value_parser = clap::value_parser!(u32).range(1..))]
+    block_size: u32,
+
+    #[arg(long, default_value_t = false)]
+    zrle: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Coder {
+    Huffman,
+    Rans,
+    Tans,
+}
+
+impl Coder {
+    fn from_u8(x: u8) -> Coder {
+        match x {
+            0 => Coder::Huffman,
+            1 => Coder::Rans,
+            _ => Coder::Tans,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Coder::Huffman => 0,
+            Coder::Rans => 1,
+            Coder::Tans => 2,
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    return u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ]);
+}
+
+fn decompress(archive: &Vec<u8>) -> Vec<u8> {
+    let coder = Coder::from_u8(archive[0]);
+    let variant = mtf::Variant::from_u8(archive[1]);
+    let step = archive[2];
+    let use_zrle = archive[3] == 1;
+    let block_size = read_u32(archive, 4) as usize;
+    let blocks = read_u32(archive, 8) as usize;
+    let primaries: Vec<u32> = (0..blocks).map(|i| read_u32(archive, 12 + 4 * i)).collect();
+    let mut shift = 12 + 4 * blocks;
+
+    let dict_len = u16::from_le_bytes([archive[shift], archive[shift + 1]]) as usize;
+    let alphabet = archive[shift + 2..shift + 2 + dict_len].to_vec();
+    shift += 2 + dict_len;
+
+    let data = &archive[shift..];
+    let symbols = match coder {
+        Coder::Huffman => huffman::decompress(data),
+        Coder::Rans => zrle::from_bytes(&ans::decode(data, ans::Coder::Rans)),
+        Coder::Tans => zrle::from_bytes(&ans::decode(data, ans::Coder::Tans)),
+    };
+    let ranks = if use_zrle {
+        zrle::decode(&symbols)
+    } else {
+        symbols.iter().map(|&r| r as u8).collect()
+    };
+    let transformed = mtf::decode(&ranks, &alphabet, variant, step);
+
+    if block_size == 0 {
+        return transformed;
+    }
+    return bwt::decode(&transformed, block_size, &primaries);
+}
+
+fn compress(data: &Vec<u8>, cli: &Cli) -> Vec<u8> {
+    let mut result = vec![cli.coder.to_u8(), cli.mtf.to_u8(), cli.step, cli.zrle as u8];
+    let (transformed, primaries) = if cli.bwt {
+        bwt::encode(data, cli.block_size as usize)
+    } else {
+        (data.clone(), Vec::new())
+    };
+    let block_size = if cli.bwt { cli.block_size } else { 0 };
+    result.extend_from_slice(&block_size.to_le_bytes());
+    result.extend_from_slice(&(primaries.len() as u32).to_le_bytes());
+    for primary in &primaries {
+        result.extend_from_slice(&primary.to_le_bytes());
+    }
+
+    let alphabet = mtf::get_alphabet(&transformed);
+    let ranks = mtf::encode(&transformed, &alphabet, cli.mtf, cli.step);
+    result.extend_from_slice(&(alphabet.len() as u16).to_le_bytes());
+    result.extend_from_slice(&alphabet);
+
+    let symbols = if cli.zrle {
+        zrle::encode(&ranks)
+    } else {
+        ranks.iter().map(|&r| r as u16).collect()
+    };
+
+    let mut archive = match cli.coder {
+        Coder::Huffman => huffman::compress(&symbols),
+        Coder::Rans => ans::encode(&zrle::to_bytes(&symbols), ans::Coder::Rans),
+        Coder::Tans => ans::encode(&zrle::to_bytes(&symbols), ans::Coder::Tans),
+    };
+    result.append(&mut archive);
+    return result;
 }
 
 fn run_decompressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut archive = Vec::new();
     input_f.read_to_end(&mut archive)?;
-    let data = huffman::decompress(&archive);
+    let data = decompress(&archive);
 
     let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
     output_f.write_all(&data)?;
@@ -36,7 +159,7 @@ fn run_compressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut data = Vec::new();
     input_f.read_to_end(&mut data)?;
-    let archive = huffman::compress(&data);
+    let archive = compress(&data, cli);
 
     let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
     output_f.write_all(&archive)?;
@@ -0,0 +1,235 @@
+use clap::ValueEnum;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Variant {
+    Mtf,
+    Mtf1,
+    MoveAhead,
+    Timestamp,
+}
+
+impl Variant {
+    pub fn from_u8(x: u8) -> Variant {
+        match x {
+            0 => Variant::Mtf,
+            1 => Variant::Mtf1,
+            2 => Variant::MoveAhead,
+            _ => Variant::Timestamp,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Variant::Mtf => 0,
+            Variant::Mtf1 => 1,
+            Variant::MoveAhead => 2,
+            Variant::Timestamp => 3,
+        }
+    }
+}
+
+const WINDOW: usize = 1024;
+const WORDS: usize = WINDOW / 64;
+
+// Recency order as a bitset of last-use times: a symbol's rank is the
+// number of symbols used after it, and moving it to the front clears one
+// bit and sets the next. Once the window fills up the live stamps are
+// packed back to its start, which happens at most once per WINDOW - 256
+// moves, so both ranks and moves are O(1) amortised.
+struct Recency {
+    stamps: [usize; 256],
+    owners: [u8; WINDOW],
+    used: [u64; WORDS],
+    now: usize,
+}
+
+impl Recency {
+    fn new(items: &[u8]) -> Self {
+        let mut recency = Self {
+            stamps: [0; 256],
+            owners: [0; WINDOW],
+            used: [0; WORDS],
+            now: 0,
+        };
+        for &byte in items.iter().rev() {
+            recency.stamp(byte);
+        }
+        return recency;
+    }
+
+    fn stamp(&mut self, byte: u8) {
+        if self.now == WINDOW {
+            self.compact();
+        }
+        self.used[self.now / 64] |= 1 << (self.now % 64);
+        self.owners[self.now] = byte;
+        self.stamps[byte as usize] = self.now;
+        self.now += 1;
+    }
+
+    fn compact(&mut self) {
+        let live: Vec<u8> = (0..self.now)
+            .filter(|&t| self.used[t / 64] >> (t % 64) & 1 == 1)
+            .map(|t| self.owners[t])
+            .collect();
+        self.used = [0; WORDS];
+        self.now = 0;
+        for byte in live {
+            self.stamp(byte);
+        }
+    }
+
+    fn touch(&mut self, byte: u8) {
+        let t = self.stamps[byte as usize];
+        self.used[t / 64] &= !(1 << (t % 64));
+        self.stamp(byte);
+    }
+
+    fn rank(&self, byte: u8) -> usize {
+        let t = self.stamps[byte as usize];
+        let mut rank = (self.used[t / 64] >> (t % 64)).count_ones() as usize - 1;
+        for word in &self.used[t / 64 + 1..] {
+            rank += word.count_ones() as usize;
+        }
+        return rank;
+    }
+
+    fn symbol(&self, rank: usize) -> u8 {
+        let mut rank = rank;
+        for i in (0..WORDS).rev() {
+            let count = self.used[i].count_ones() as usize;
+            if rank < count {
+                let mut word = self.used[i];
+                for _ in 0..rank {
+                    word &= !(1 << (63 - word.leading_zeros()));
+                }
+                return self.owners[i * 64 + 63 - word.leading_zeros() as usize];
+            }
+            rank -= count;
+        }
+        panic!("MTF rank is outside the alphabet");
+    }
+}
+
+// MTF and MTF-1 only ever move symbols to the front, so they run on
+// `Recency`. Move-ahead-k shifts k entries of the array, which `positions`
+// makes O(1) per symbol for a fixed k; the timestamp rule has to look
+// through the symbols ahead of the current one for its target anyway.
+struct List {
+    variant: Variant,
+    step: usize,
+    recency: Option<Recency>,
+    items: Vec<u8>,
+    positions: [usize; 256],
+    time: i64,
+    last: [i64; 256],
+    previous: [i64; 256],
+}
+
+impl List {
+    fn new(alphabet: &Vec<u8>, variant: Variant, step: u8) -> Self {
+        let mut positions = [0; 256];
+        for (i, &byte) in alphabet.iter().enumerate() {
+            positions[byte as usize] = i;
+        }
+        let recency = match variant {
+            Variant::Mtf | Variant::Mtf1 => Some(Recency::new(alphabet)),
+            _ => None,
+        };
+        Self {
+            variant,
+            step: step as usize,
+            recency,
+            items: alphabet.clone(),
+            positions,
+            time: 0,
+            last: [-1; 256],
+            previous: [-1; 256],
+        }
+    }
+
+    fn target(&self, byte: u8, pos: usize) -> usize {
+        match self.variant {
+            Variant::Mtf => 0,
+            Variant::Mtf1 => {
+                if pos <= 1 {
+                    0
+                } else {
+                    1
+                }
+            }
+            Variant::MoveAhead => pos.saturating_sub(self.step),
+            Variant::Timestamp => {
+                let since = self.last[byte as usize];
+                self.items[..pos]
+                    .iter()
+                    .position(|&y| self.previous[y as usize] < since)
+                    .unwrap_or(pos)
+            }
+        }
+    }
+
+    fn promote(&mut self, byte: u8, pos: usize) {
+        let target = self.target(byte, pos);
+        if let Some(recency) = &mut self.recency {
+            // MTF-1 puts the symbol second by moving the old front back
+            // ahead of it.
+            if target < pos {
+                let front = recency.symbol(0);
+                recency.touch(byte);
+                if target == 1 {
+                    recency.touch(front);
+                }
+            }
+        } else {
+            self.items.copy_within(target..pos, target + 1);
+            self.items[target] = byte;
+            for i in target..=pos {
+                self.positions[self.items[i] as usize] = i;
+            }
+        }
+
+        self.previous[byte as usize] = self.last[byte as usize];
+        self.last[byte as usize] = self.time;
+        self.time += 1;
+    }
+
+    fn encode(&mut self, byte: u8) -> u8 {
+        let pos = match &self.recency {
+            Some(recency) => recency.rank(byte),
+            None => self.positions[byte as usize],
+        };
+        self.promote(byte, pos);
+        return pos as u8;
+    }
+
+    fn decode(&mut self, pos: u8) -> u8 {
+        let byte = match &self.recency {
+            Some(recency) => recency.symbol(pos as usize),
+            None => self.items[pos as usize],
+        };
+        self.promote(byte, pos as usize);
+        return byte;
+    }
+}
+
+pub fn get_alphabet(data: &Vec<u8>) -> Vec<u8> {
+    let mut alphabet = HashSet::new();
+    data.iter().for_each(|v| {
+        alphabet.insert(*v);
+    });
+    let mut alphabet = Vec::from_iter(alphabet);
+    alphabet.sort();
+    return alphabet;
+}
+
+pub fn encode(data: &Vec<u8>, alphabet: &Vec<u8>, variant: Variant, step: u8) -> Vec<u8> {
+    let mut list = List::new(alphabet, variant, step);
+    return data.iter().map(|&byte| list.encode(byte)).collect();
+}
+
+pub fn decode(ranks: &Vec<u8>, alphabet: &Vec<u8>, variant: Variant, step: u8) -> Vec<u8> {
+    let mut list = List::new(alphabet, variant, step);
+    return ranks.iter().map(|&pos| list.decode(pos)).collect();
+}
@@ -0,0 +1,79 @@
+pub const RUNA: u16 = 0;
+pub const RUNB: u16 = 1;
+
+fn dump_run(encoded: &mut Vec<u16>, run: usize) {
+    if run == 0 {
+        return;
+    }
+    let mut pending = run - 1;
+    loop {
+        encoded.push(if pending & 1 == 1 { RUNB } else { RUNA });
+        if pending < 2 {
+            break;
+        }
+        pending = (pending - 2) / 2;
+    }
+}
+
+pub fn encode(ranks: &Vec<u8>) -> Vec<u16> {
+    let mut encoded = Vec::new();
+    let mut run = 0;
+    for &rank in ranks {
+        if rank == 0 {
+            run += 1;
+        } else {
+            dump_run(&mut encoded, run);
+            run = 0;
+            encoded.push(rank as u16 + 1);
+        }
+    }
+    dump_run(&mut encoded, run);
+    return encoded;
+}
+
+pub fn decode(symbols: &Vec<u16>) -> Vec<u8> {
+    let mut ranks = Vec::new();
+    let mut run = 0;
+    let mut weight = 1;
+    for &symbol in symbols {
+        if symbol == RUNA || symbol == RUNB {
+            run += if symbol == RUNA { weight } else { 2 * weight };
+            weight *= 2;
+        } else {
+            ranks.resize(ranks.len() + run, 0);
+            run = 0;
+            weight = 1;
+            ranks.push((symbol - 1) as u8);
+        }
+    }
+    ranks.resize(ranks.len() + run, 0);
+    return ranks;
+}
+
+pub fn to_bytes(symbols: &Vec<u16>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(symbols.len());
+    for &symbol in symbols {
+        if symbol < 255 {
+            bytes.push(symbol as u8);
+        } else {
+            bytes.push(255);
+            bytes.push((symbol - 255) as u8);
+        }
+    }
+    return bytes;
+}
+
+pub fn from_bytes(bytes: &Vec<u8>) -> Vec<u16> {
+    let mut symbols = Vec::with_capacity(bytes.len());
+    let mut ptr = 0;
+    while ptr < bytes.len() {
+        if bytes[ptr] < 255 {
+            symbols.push(bytes[ptr] as u16);
+            ptr += 1;
+        } else {
+            symbols.push(255 + bytes[ptr + 1] as u16);
+            ptr += 2;
+        }
+    }
+    return symbols;
+}
@@ -0,0 +1,213 @@
+use bits::{BitReader, BitWriter};
+
+const MAX_BITS: usize = 15;
+const RUN_SYMBOLS: usize = 19;
+
+pub fn lengths(freqs: &[u32], limit: u32) -> Vec<u8> {
+    let mut result = vec![0u8; freqs.len()];
+    let mut leaves: Vec<(u64, Vec<u16>)> = freqs
+        .iter()
+        .enumerate()
+        .filter(|(_, &f)| f > 0)
+        .map(|(s, &f)| (f as u64, vec![s as u16]))
+        .collect();
+    if leaves.len() == 1 {
+        result[leaves[0].1[0] as usize] = 1;
+    }
+    if leaves.len() <= 1 {
+        return result;
+    }
+    assert!(
+        leaves.len() <= 1 << limit,
+        "too many symbols for the code length limit"
+    );
+    leaves.sort_by_key(|leaf| leaf.0);
+
+    let mut list = leaves.clone();
+    for _ in 1..limit {
+        let mut merged = Vec::with_capacity(leaves.len() + list.len() / 2);
+        let mut packages = list.chunks_exact(2).map(|pair| {
+            let mut symbols = pair[0].1.clone();
+            symbols.extend_from_slice(&pair[1].1);
+            (pair[0].0 + pair[1].0, symbols)
+        });
+        let mut package = packages.next();
+        let mut i = 0;
+        while i < leaves.len() || package.is_some() {
+            let take_leaf = match &package {
+                Some(p) => i < leaves.len() && leaves[i].0 <= p.0,
+                None => true,
+            };
+            if take_leaf {
+                merged.push(leaves[i].clone());
+                i += 1;
+            } else {
+                merged.push(package.take().unwrap());
+                package = packages.next();
+            }
+        }
+        list = merged;
+    }
+
+    for (_, symbols) in &list[..2 * leaves.len() - 2] {
+        for &s in symbols {
+            result[s as usize] += 1;
+        }
+    }
+    return result;
+}
+
+fn reverse(code: u32, length: u8) -> u32 {
+    return code.reverse_bits() >> (32 - length as u32);
+}
+
+pub fn codes(lengths: &[u8]) -> Vec<u32> {
+    let mut counts = [0u32; MAX_BITS + 1];
+    lengths.iter().for_each(|&l| counts[l as usize] += 1);
+    counts[0] = 0;
+
+    let mut next = [0u32; MAX_BITS + 2];
+    for bits in 1..=MAX_BITS {
+        next[bits + 1] = (next[bits] + counts[bits]) << 1;
+    }
+
+    let mut result = vec![0; lengths.len()];
+    for (s, &l) in lengths.iter().enumerate() {
+        if l > 0 {
+            result[s] = reverse(next[l as usize], l);
+            next[l as usize] += 1;
+        }
+    }
+    return result;
+}
+
+pub struct Decoder {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Decoder {
+    pub fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        lengths.iter().for_each(|&l| counts[l as usize] += 1);
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for bits in 1..=MAX_BITS {
+            offsets[bits + 1] = offsets[bits] + counts[bits];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (s, &l) in lengths.iter().enumerate() {
+            if l > 0 {
+                symbols[offsets[l as usize] as usize] = s as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+        return Self { counts, symbols };
+    }
+
+    pub fn decode(&self, reader: &mut BitReader) -> u16 {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for bits in 1..=MAX_BITS {
+            code |= reader.read(1) as i32;
+            let count = self.counts[bits] as i32;
+            if code - first < count {
+                return self.symbols[(index + code - first) as usize];
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        panic!("invalid Huffman code");
+    }
+}
+
+pub fn length_runs(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let l = lengths[i];
+        let mut run = 1;
+        while i + run < lengths.len() && lengths[i + run] == l {
+            run += 1;
+        }
+        i += run;
+
+        if l == 0 {
+            while run >= 11 {
+                let n = std::cmp::min(run, 138);
+                result.push((18, (n - 11) as u8));
+                run -= n;
+            }
+            if run >= 3 {
+                result.push((17, (run - 3) as u8));
+                run = 0;
+            }
+        } else {
+            result.push((l, 0));
+            run -= 1;
+            while run >= 3 {
+                let n = std::cmp::min(run, 6);
+                result.push((16, (n - 3) as u8));
+                run -= n;
+            }
+        }
+        for _ in 0..run {
+            result.push((l, 0));
+        }
+    }
+    return result;
+}
+
+pub fn run_extra(symbol: u8) -> u32 {
+    match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
+
+pub fn read_runs(reader: &mut BitReader, decoder: &Decoder, count: usize) -> Vec<u8> {
+    let mut lengths = Vec::with_capacity(count);
+    while lengths.len() < count {
+        let s = decoder.decode(reader) as u8;
+        let extra = reader.read(run_extra(s));
+        let (value, repeat) = match s {
+            16 => {
+                let previous = *lengths.last().expect("repeat without a previous length");
+                (previous, 3 + extra)
+            }
+            17 => (0, 3 + extra),
+            18 => (0, 11 + extra),
+            _ => (s, 1),
+        };
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    assert!(lengths.len() == count, "code lengths overrun the table");
+    return lengths;
+}
+
+pub fn write_lengths(writer: &mut BitWriter, lengths: &[u8]) {
+    let runs = length_runs(lengths);
+    let mut freqs = vec![0u32; RUN_SYMBOLS];
+    runs.iter().for_each(|&(s, _)| freqs[s as usize] += 1);
+    let run_lengths = self::lengths(&freqs, 7);
+    let run_codes = codes(&run_lengths);
+    for &l in &run_lengths {
+        writer.write(l as u32, 3);
+    }
+    for &(s, extra) in &runs {
+        writer.write(run_codes[s as usize], run_lengths[s as usize] as u32);
+        writer.write(extra as u32, run_extra(s));
+    }
+}
+
+pub fn read_lengths(reader: &mut BitReader, count: usize) -> Vec<u8> {
+    let run_lengths: Vec<u8> = (0..RUN_SYMBOLS).map(|_| reader.read(3) as u8).collect();
+    return read_runs(reader, &Decoder::new(&run_lengths), count);
+}
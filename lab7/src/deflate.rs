@@ -0,0 +1,342 @@
+use crate::canonical;
+use crate::matchfinder::Params;
+use crate::parser::{parse, Costs, Tokens};
+use bits::{BitReader, BitWriter};
+
+pub const WINDOW_SIZE: usize = 32768;
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 258;
+const BLOCK_TOKENS: usize = 16384;
+const STORED_MAX: usize = 65535;
+const END_OF_BLOCK: usize = 256;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn length_code(length: usize) -> usize {
+    return LENGTH_BASE
+        .iter()
+        .rposition(|&b| b as usize <= length)
+        .unwrap();
+}
+
+fn dist_code(dist: usize) -> usize {
+    return DIST_BASE.iter().rposition(|&b| b as usize <= dist).unwrap();
+}
+
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+    let mut lit = vec![8u8; 288];
+    lit[144..256].fill(9);
+    lit[256..280].fill(7);
+    return (lit, vec![5u8; 30]);
+}
+
+struct Block<'a> {
+    tokens: &'a [(u32, u32)],
+    bytes: &'a [u8],
+    lit_freqs: Vec<u32>,
+    dist_freqs: Vec<u32>,
+}
+
+impl<'a> Block<'a> {
+    fn new(tokens: &'a [(u32, u32)], bytes: &'a [u8]) -> Self {
+        let mut lit_freqs = vec![0u32; 286];
+        let mut dist_freqs = vec![0u32; 30];
+        let mut ptr = 0;
+        for &(offset, l) in tokens {
+            if l == 0 {
+                lit_freqs[bytes[ptr] as usize] += 1;
+                ptr += 1;
+            } else {
+                lit_freqs[257 + length_code(l as usize)] += 1;
+                dist_freqs[dist_code(offset as usize)] += 1;
+                ptr += l as usize;
+            }
+        }
+        lit_freqs[END_OF_BLOCK] += 1;
+        return Self {
+            tokens,
+            bytes,
+            lit_freqs,
+            dist_freqs,
+        };
+    }
+
+    fn data_bits(&self, lit_lengths: &[u8], dist_lengths: &[u8]) -> u64 {
+        let mut bits = 0;
+        for (s, &f) in self.lit_freqs.iter().enumerate() {
+            bits += f as u64 * lit_lengths[s] as u64;
+            if s > END_OF_BLOCK {
+                bits += f as u64 * LENGTH_EXTRA[s - 257] as u64;
+            }
+        }
+        for (s, &f) in self.dist_freqs.iter().enumerate() {
+            bits += f as u64 * (dist_lengths[s] + DIST_EXTRA[s]) as u64;
+        }
+        return bits;
+    }
+
+    fn write_data(&self, writer: &mut BitWriter, lit_lengths: &[u8], dist_lengths: &[u8]) {
+        let lit_codes = canonical::codes(lit_lengths);
+        let dist_codes = canonical::codes(dist_lengths);
+        let mut ptr = 0;
+        for &(offset, l) in self.tokens {
+            if l == 0 {
+                let s = self.bytes[ptr] as usize;
+                writer.write(lit_codes[s], lit_lengths[s] as u32);
+                ptr += 1;
+            } else {
+                let code = length_code(l as usize);
+                writer.write(lit_codes[257 + code], lit_lengths[257 + code] as u32);
+                writer.write(l - LENGTH_BASE[code] as u32, LENGTH_EXTRA[code] as u32);
+                let code = dist_code(offset as usize);
+                writer.write(dist_codes[code], dist_lengths[code] as u32);
+                writer.write(offset - DIST_BASE[code] as u32, DIST_EXTRA[code] as u32);
+                ptr += l as usize;
+            }
+        }
+        writer.write(lit_codes[END_OF_BLOCK], lit_lengths[END_OF_BLOCK] as u32);
+    }
+}
+
+struct Header {
+    lit_lengths: Vec<u8>,
+    dist_lengths: Vec<u8>,
+    runs: Vec<(u8, u8)>,
+    cl_lengths: Vec<u8>,
+    hlit: usize,
+    hdist: usize,
+    hclen: usize,
+}
+
+impl Header {
+    fn new(block: &Block) -> Self {
+        let lit_lengths = canonical::lengths(&block.lit_freqs, 15);
+        let mut dist_lengths = canonical::lengths(&block.dist_freqs, 15);
+        if dist_lengths.iter().all(|&l| l == 0) {
+            dist_lengths[0] = 1;
+        }
+        let hlit = 257
+            + lit_lengths[257..]
+                .iter()
+                .rposition(|&l| l > 0)
+                .map_or(0, |p| p + 1);
+        let hdist = 1 + dist_lengths.iter().rposition(|&l| l > 0).unwrap_or(0);
+
+        let mut all = lit_lengths[..hlit].to_vec();
+        all.extend_from_slice(&dist_lengths[..hdist]);
+        let runs = canonical::length_runs(&all);
+        let mut cl_freqs = vec![0u32; 19];
+        runs.iter().for_each(|&(s, _)| cl_freqs[s as usize] += 1);
+        let mut cl_lengths = canonical::lengths(&cl_freqs, 7);
+        if cl_lengths.iter().filter(|&&l| l > 0).count() == 1 {
+            let unused = if cl_lengths[0] == 0 { 0 } else { 1 };
+            cl_lengths[unused] = 1;
+        }
+        let hclen = 4 + CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|&s| cl_lengths[s] > 0)
+            .map_or(0, |p| std::cmp::max(p + 1, 4) - 4);
+
+        return Self {
+            lit_lengths,
+            dist_lengths,
+            runs,
+            cl_lengths,
+            hlit,
+            hdist,
+            hclen,
+        };
+    }
+
+    fn bits(&self) -> u64 {
+        let mut bits = 5 + 5 + 4 + 3 * self.hclen as u64;
+        for &(s, _) in &self.runs {
+            bits += (self.cl_lengths[s as usize] as u32 + canonical::run_extra(s)) as u64;
+        }
+        return bits;
+    }
+
+    fn write(&self, writer: &mut BitWriter) {
+        writer.write(self.hlit as u32 - 257, 5);
+        writer.write(self.hdist as u32 - 1, 5);
+        writer.write(self.hclen as u32 - 4, 4);
+        for &s in &CODE_LENGTH_ORDER[..self.hclen] {
+            writer.write(self.cl_lengths[s] as u32, 3);
+        }
+        let cl_codes = canonical::codes(&self.cl_lengths);
+        for &(s, extra) in &self.runs {
+            writer.write(cl_codes[s as usize], self.cl_lengths[s as usize] as u32);
+            writer.write(extra as u32, canonical::run_extra(s));
+        }
+    }
+}
+
+fn write_stored(writer: &mut BitWriter, bytes: &[u8], last: bool) {
+    let mut chunks: Vec<&[u8]> = bytes.chunks(STORED_MAX).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    let count = chunks.len();
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        writer.write((last && i + 1 == count) as u32, 1);
+        writer.write(0, 2);
+        writer.align();
+        writer.write(chunk.len() as u32, 16);
+        writer.write(!chunk.len() as u32 & 0xFFFF, 16);
+        writer.result.extend_from_slice(chunk);
+    }
+}
+
+fn stored_bits(bytes: &[u8]) -> u64 {
+    let chunks = std::cmp::max((bytes.len() + STORED_MAX - 1) / STORED_MAX, 1);
+    return chunks as u64 * (3 + 7 + 32) + bytes.len() as u64 * 8;
+}
+
+fn write_block(writer: &mut BitWriter, block: &Block, last: bool) {
+    let (fixed_lit, fixed_dist) = fixed_lengths();
+    let fixed = 3 + block.data_bits(&fixed_lit, &fixed_dist);
+    let header = Header::new(block);
+    let dynamic = 3 + header.bits() + block.data_bits(&header.lit_lengths, &header.dist_lengths);
+    let stored = stored_bits(block.bytes);
+
+    if stored < fixed && stored < dynamic {
+        write_stored(writer, block.bytes, last);
+    } else if fixed <= dynamic {
+        writer.write(last as u32, 1);
+        writer.write(1, 2);
+        block.write_data(writer, &fixed_lit, &fixed_dist);
+    } else {
+        writer.write(last as u32, 1);
+        writer.write(2, 2);
+        header.write(writer);
+        block.write_data(writer, &header.lit_lengths, &header.dist_lengths);
+    }
+}
+
+pub fn compress(data: &Vec<u8>, dict: &[u8], params: Params) -> Vec<u8> {
+    let input = [dict, data].concat();
+    let tokens = Tokens {
+        min_length: MIN_LENGTH,
+        reserve: 0,
+        costs: Costs {
+            literal: 9,
+            pair: 20,
+        },
+    };
+    let steps = parse(&input, dict.len(), WINDOW_SIZE, MAX_LENGTH, params, &tokens);
+
+    let mut writer = BitWriter::new();
+    let blocks: Vec<&[(u32, u32)]> = steps.chunks(BLOCK_TOKENS).collect();
+    if blocks.is_empty() {
+        write_block(&mut writer, &Block::new(&[], &[]), true);
+    }
+    let mut ptr = dict.len();
+    for (i, tokens) in blocks.iter().enumerate() {
+        let size: usize = tokens
+            .iter()
+            .map(|&(_, l)| std::cmp::max(l, 1) as usize)
+            .sum();
+        let block = Block::new(tokens, &input[ptr..ptr + size]);
+        write_block(&mut writer, &block, i + 1 == blocks.len());
+        ptr += size;
+    }
+    return writer.finish();
+}
+
+fn read_dynamic(reader: &mut BitReader) -> (canonical::Decoder, canonical::Decoder) {
+    let hlit = reader.read(5) as usize + 257;
+    let hdist = reader.read(5) as usize + 1;
+    let hclen = reader.read(4) as usize + 4;
+    let mut cl_lengths = vec![0u8; 19];
+    for &s in &CODE_LENGTH_ORDER[..hclen] {
+        cl_lengths[s] = reader.read(3) as u8;
+    }
+    let cl_decoder = canonical::Decoder::new(&cl_lengths);
+
+    let lengths = canonical::read_runs(reader, &cl_decoder, hlit + hdist);
+
+    return (
+        canonical::Decoder::new(&lengths[..hlit]),
+        canonical::Decoder::new(&lengths[hlit..]),
+    );
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    result: &mut Vec<u8>,
+    lit: &canonical::Decoder,
+    dist: &canonical::Decoder,
+) {
+    loop {
+        let s = lit.decode(reader) as usize;
+        if s < END_OF_BLOCK {
+            result.push(s as u8);
+        } else if s == END_OF_BLOCK {
+            return;
+        } else {
+            let code = s - 257;
+            assert!(code < 29, "invalid length code");
+            let l = LENGTH_BASE[code] as usize + reader.read(LENGTH_EXTRA[code] as u32) as usize;
+            let code = dist.decode(reader) as usize;
+            assert!(code < 30, "invalid distance code");
+            let offset = DIST_BASE[code] as usize + reader.read(DIST_EXTRA[code] as u32) as usize;
+            assert!(offset <= result.len(), "distance points before the start");
+            let start = result.len() - offset;
+            for j in 0..l {
+                result.push(result[start + j]);
+            }
+        }
+    }
+}
+
+pub fn decompress_from(reader: &mut BitReader, dict: &[u8]) -> Vec<u8> {
+    let mut result = dict.to_vec();
+    let (fixed_lit, fixed_dist) = fixed_lengths();
+    loop {
+        let last = reader.read(1) == 1;
+        match reader.read(2) {
+            0 => {
+                reader.align();
+                let header = reader.read_bytes(4);
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                assert!(len == !nlen, "stored block length check failed");
+                result.extend_from_slice(reader.read_bytes(len as usize));
+            }
+            1 => {
+                let lit = canonical::Decoder::new(&fixed_lit);
+                let dist = canonical::Decoder::new(&fixed_dist);
+                inflate_block(reader, &mut result, &lit, &dist);
+            }
+            2 => {
+                let (lit, dist) = read_dynamic(reader);
+                inflate_block(reader, &mut result, &lit, &dist);
+            }
+            _ => panic!("invalid block type"),
+        }
+        if last {
+            return result.split_off(dict.len());
+        }
+    }
+}
+
+pub fn decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    return decompress_from(&mut BitReader::new(archive), dict);
+}
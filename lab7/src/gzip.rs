@@ -0,0 +1,126 @@
+use crate::deflate;
+use crate::matchfinder::Params;
+use bits::BitReader;
+use checksum::{adler32, crc32};
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const CM_DEFLATE: u8 = 8;
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+const OS_UNKNOWN: u8 = 255;
+const ZLIB_CMF: u8 = 0x78;
+const ZLIB_LEVEL: u8 = 0x80;
+const ZLIB_FDICT: u8 = 0x20;
+
+pub fn is_gzip(archive: &[u8]) -> bool {
+    return archive.len() >= 2 && archive[..2] == GZIP_MAGIC;
+}
+
+pub fn is_zlib(archive: &[u8]) -> bool {
+    return archive.len() >= 2
+        && archive[0] & 0x0F == CM_DEFLATE
+        && archive[0] >> 4 <= 7
+        && ((archive[0] as u16) << 8 | archive[1] as u16) % 31 == 0;
+}
+
+pub fn gzip_compress(data: &Vec<u8>, params: Params) -> Vec<u8> {
+    let mut result = GZIP_MAGIC.to_vec();
+    result.extend_from_slice(&[CM_DEFLATE, 0, 0, 0, 0, 0, 0, OS_UNKNOWN]);
+    result.append(&mut deflate::compress(data, &[], params));
+    result.extend_from_slice(&crc32(data).to_le_bytes());
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    return result;
+}
+
+fn skip_string(archive: &[u8], mut caret: usize) -> usize {
+    while archive[caret] != 0 {
+        caret += 1;
+    }
+    return caret + 1;
+}
+
+pub fn gzip_decompress(archive: &Vec<u8>) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut caret = 0;
+    while caret < archive.len() {
+        let member = &archive[caret..];
+        assert!(is_gzip(member), "not a gzip member");
+        assert!(
+            member[2] == CM_DEFLATE,
+            "unsupported gzip compression method"
+        );
+        let flags = member[3];
+        let mut start = 10;
+        if flags & FEXTRA != 0 {
+            start += 2 + u16::from_le_bytes([member[start], member[start + 1]]) as usize;
+        }
+        if flags & FNAME != 0 {
+            start = skip_string(member, start);
+        }
+        if flags & FCOMMENT != 0 {
+            start = skip_string(member, start);
+        }
+        if flags & FHCRC != 0 {
+            start += 2;
+        }
+
+        let mut reader = BitReader::new(&member[start..]);
+        let data = deflate::decompress_from(&mut reader, &[]);
+        let end = start + reader.position();
+        let trailer = &member[end..end + 8];
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        assert!(crc == crc32(&data), "gzip CRC-32 mismatch");
+        assert!(size == data.len() as u32, "gzip size mismatch");
+
+        result.extend_from_slice(&data);
+        caret += end + 8;
+    }
+    return result;
+}
+
+pub fn zlib_compress(data: &Vec<u8>, dict: &[u8], params: Params) -> Vec<u8> {
+    let mut flg = ZLIB_LEVEL;
+    if !dict.is_empty() {
+        flg |= ZLIB_FDICT;
+    }
+    flg += (31 - ((ZLIB_CMF as u16) << 8 | flg as u16) % 31) as u8 % 31;
+    let mut result = vec![ZLIB_CMF, flg];
+    if !dict.is_empty() {
+        result.extend_from_slice(&adler32(dict).to_be_bytes());
+    }
+    result.append(&mut deflate::compress(data, dict, params));
+    result.extend_from_slice(&adler32(data).to_be_bytes());
+    return result;
+}
+
+pub fn zlib_decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    assert!(is_zlib(archive), "not a zlib stream");
+    let mut start = 2;
+    if archive[1] & ZLIB_FDICT != 0 {
+        let id = u32::from_be_bytes([archive[2], archive[3], archive[4], archive[5]]);
+        assert!(
+            !dict.is_empty(),
+            "stream needs a preset dictionary, pass --dict"
+        );
+        assert!(
+            id == adler32(dict),
+            "preset dictionary does not match the stream"
+        );
+        start += 4;
+    }
+    let dict = if start > 2 { dict } else { &[] };
+    let mut reader = BitReader::new(&archive[start..]);
+    let data = deflate::decompress_from(&mut reader, dict);
+    let end = start + reader.position();
+    let adler = u32::from_be_bytes([
+        archive[end],
+        archive[end + 1],
+        archive[end + 2],
+        archive[end + 3],
+    ]);
+    assert!(adler == adler32(&data), "zlib Adler-32 mismatch");
+    return data;
+}
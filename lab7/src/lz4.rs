@@ -0,0 +1,348 @@
+use checksum::adler32;
+
+const MIN_MATCH: usize = 4;
+const LAST_LITERALS: usize = 5;
+const MF_LIMIT: usize = 12;
+const MAX_OFFSET: usize = 65535;
+const HASH_BITS: u32 = 16;
+const SKIP_TRIGGER: u32 = 6;
+
+const MAGIC: u32 = 0x184D2204;
+const SKIPPABLE_MAGIC: u32 = 0x184D2A50;
+const BLOCK_SIZE: usize = 4 << 20;
+const BLOCK_UNCOMPRESSED: u32 = 1 << 31;
+const FLG_VERSION: u8 = 0x40;
+const FLG_BLOCK_INDEPENDENT: u8 = 0x20;
+const FLG_BLOCK_CHECKSUM: u8 = 0x10;
+const FLG_CONTENT_SIZE: u8 = 0x08;
+const FLG_CONTENT_CHECKSUM: u8 = 0x04;
+const FLG_DICT_ID: u8 = 0x01;
+const BD_4MB: u8 = 7 << 4;
+
+const PRIME1: u32 = 0x9E3779B1;
+const PRIME2: u32 = 0x85EBCA77;
+const PRIME3: u32 = 0xC2B2AE3D;
+const PRIME4: u32 = 0x27D4EB2F;
+const PRIME5: u32 = 0x165667B1;
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    return u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+}
+
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    let round = |acc: u32, lane: u32| -> u32 {
+        acc.wrapping_add(lane.wrapping_mul(PRIME2))
+            .rotate_left(13)
+            .wrapping_mul(PRIME1)
+    };
+
+    let mut pos = 0;
+    let mut h = if data.len() >= 16 {
+        let mut v = [
+            seed.wrapping_add(PRIME1).wrapping_add(PRIME2),
+            seed.wrapping_add(PRIME2),
+            seed,
+            seed.wrapping_sub(PRIME1),
+        ];
+        while pos + 16 <= data.len() {
+            for (i, acc) in v.iter_mut().enumerate() {
+                *acc = round(*acc, read_u32(data, pos + 4 * i));
+            }
+            pos += 16;
+        }
+        v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(PRIME5)
+    };
+    h = h.wrapping_add(data.len() as u32);
+
+    while pos + 4 <= data.len() {
+        h = h.wrapping_add(read_u32(data, pos).wrapping_mul(PRIME3));
+        h = h.rotate_left(17).wrapping_mul(PRIME4);
+        pos += 4;
+    }
+    while pos < data.len() {
+        h = h.wrapping_add((data[pos] as u32).wrapping_mul(PRIME5));
+        h = h.rotate_left(11).wrapping_mul(PRIME1);
+        pos += 1;
+    }
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(PRIME2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(PRIME3);
+    h ^= h >> 16;
+    return h;
+}
+
+fn hash(sequence: u32) -> usize {
+    return (sequence.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize;
+}
+
+fn write_length(result: &mut Vec<u8>, mut length: usize) {
+    while length >= 255 {
+        result.push(255);
+        length -= 255;
+    }
+    result.push(length as u8);
+}
+
+fn write_sequence(result: &mut Vec<u8>, literals: &[u8], offset: usize, length: usize) {
+    let lit_nibble = std::cmp::min(literals.len(), 15);
+    let match_nibble = if offset > 0 {
+        std::cmp::min(length - MIN_MATCH, 15)
+    } else {
+        0
+    };
+    result.push((lit_nibble << 4 | match_nibble) as u8);
+    if literals.len() >= 15 {
+        write_length(result, literals.len() - 15);
+    }
+    result.extend_from_slice(literals);
+    if offset > 0 {
+        result.extend_from_slice(&(offset as u16).to_le_bytes());
+        if length - MIN_MATCH >= 15 {
+            write_length(result, length - MIN_MATCH - 15);
+        }
+    }
+}
+
+pub fn compress_block(data: &[u8], start: usize) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len() + data.len() / 255 + 16);
+    let mut table = vec![0u32; 1 << HASH_BITS];
+    let mut anchor = start;
+
+    if data.len() > start + MF_LIMIT {
+        let match_limit = data.len() - LAST_LITERALS;
+        let search_end = data.len() - MF_LIMIT;
+        for pos in 0..=start {
+            table[hash(read_u32(data, pos))] = pos as u32;
+        }
+        let mut ptr = start + 1;
+        let mut attempts = 1u32 << SKIP_TRIGGER;
+        while ptr < search_end {
+            let h = hash(read_u32(data, ptr));
+            let candidate = table[h] as usize;
+            table[h] = ptr as u32;
+            if ptr - candidate > MAX_OFFSET || read_u32(data, candidate) != read_u32(data, ptr) {
+                ptr += (attempts >> SKIP_TRIGGER) as usize;
+                attempts += 1;
+                continue;
+            }
+            attempts = 1 << SKIP_TRIGGER;
+
+            let mut start = ptr;
+            let mut source = candidate;
+            while start > anchor && source > 0 && data[start - 1] == data[source - 1] {
+                start -= 1;
+                source -= 1;
+            }
+            let mut end = ptr + MIN_MATCH;
+            while end < match_limit && data[end] == data[source + end - start] {
+                end += 1;
+            }
+
+            write_sequence(
+                &mut result,
+                &data[anchor..start],
+                start - source,
+                end - start,
+            );
+            anchor = end;
+            ptr = end;
+            if ptr >= 2 && ptr - 2 < search_end {
+                table[hash(read_u32(data, ptr - 2))] = (ptr - 2) as u32;
+            }
+        }
+    }
+
+    write_sequence(&mut result, &data[anchor..], 0, 0);
+    return result;
+}
+
+fn read_length(block: &[u8], caret: &mut usize, nibble: usize) -> usize {
+    let mut length = nibble;
+    if nibble == 15 {
+        loop {
+            let byte = block[*caret];
+            *caret += 1;
+            length += byte as usize;
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    return length;
+}
+
+pub fn decompress_block(block: &[u8], result: &mut Vec<u8>) {
+    let mut caret = 0;
+    while caret < block.len() {
+        let token = block[caret] as usize;
+        caret += 1;
+
+        let literals = read_length(block, &mut caret, token >> 4);
+        assert!(
+            caret + literals <= block.len(),
+            "literal run past the block end"
+        );
+        result.extend_from_slice(&block[caret..caret + literals]);
+        caret += literals;
+        if caret == block.len() {
+            break;
+        }
+
+        let offset = u16::from_le_bytes([block[caret], block[caret + 1]]) as usize;
+        caret += 2;
+        let length = read_length(block, &mut caret, token & 15) + MIN_MATCH;
+        assert!(offset > 0 && offset <= result.len(), "invalid match offset");
+        let start = result.len() - offset;
+        if offset >= length {
+            result.extend_from_within(start..start + length);
+        } else {
+            for j in 0..length {
+                result.push(result[start + j]);
+            }
+        }
+    }
+}
+
+fn window(dict: &[u8]) -> &[u8] {
+    return &dict[dict.len().saturating_sub(MAX_OFFSET)..];
+}
+
+pub fn compress(data: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let dict = window(dict);
+    let mut result = (data.len() as u32).to_le_bytes().to_vec();
+    result.append(&mut compress_block(&[dict, data].concat(), dict.len()));
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let dict = window(dict);
+    let length = read_u32(archive, 0) as usize;
+    let mut result = dict.to_vec();
+    decompress_block(&archive[4..], &mut result);
+    assert!(result.len() == dict.len() + length, "block size mismatch");
+    return result.split_off(dict.len());
+}
+
+pub fn is_frame(archive: &[u8]) -> bool {
+    if archive.len() < 7 || read_u32(archive, 0) != MAGIC {
+        return false;
+    }
+    let flg = archive[4];
+    let descriptor = if flg & FLG_CONTENT_SIZE != 0 {
+        2 + 8
+    } else {
+        2
+    };
+    let descriptor = descriptor + if flg & FLG_DICT_ID != 0 { 4 } else { 0 };
+    return flg & 0xC0 == FLG_VERSION
+        && archive.len() > 4 + descriptor
+        && archive[4 + descriptor] == (xxh32(&archive[4..4 + descriptor], 0) >> 8) as u8;
+}
+
+pub fn compress_frame(data: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let mut result = MAGIC.to_le_bytes().to_vec();
+    let mut descriptor = vec![
+        FLG_VERSION | FLG_BLOCK_INDEPENDENT | FLG_CONTENT_CHECKSUM,
+        BD_4MB,
+    ];
+    if !dict.is_empty() {
+        descriptor[0] |= FLG_DICT_ID;
+        descriptor.extend_from_slice(&adler32(dict).to_le_bytes());
+    }
+    result.extend_from_slice(&descriptor);
+    result.push((xxh32(&descriptor, 0) >> 8) as u8);
+
+    let dict = window(dict);
+    for chunk in data.chunks(BLOCK_SIZE) {
+        let block = compress_block(&[dict, chunk].concat(), dict.len());
+        if block.len() < chunk.len() {
+            result.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            result.extend_from_slice(&block);
+        } else {
+            result.extend_from_slice(&(chunk.len() as u32 | BLOCK_UNCOMPRESSED).to_le_bytes());
+            result.extend_from_slice(chunk);
+        }
+    }
+    result.extend_from_slice(&0u32.to_le_bytes());
+    result.extend_from_slice(&xxh32(data, 0).to_le_bytes());
+    return result;
+}
+
+pub fn decompress_frame(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut caret = 0;
+    while caret < archive.len() {
+        let magic = read_u32(archive, caret);
+        if magic & 0xFFFFFFF0 == SKIPPABLE_MAGIC {
+            caret += 8 + read_u32(archive, caret + 4) as usize;
+            continue;
+        }
+        assert!(is_frame(&archive[caret..]), "not an LZ4 frame");
+        let flg = archive[caret + 4];
+        caret += 6;
+        if flg & FLG_CONTENT_SIZE != 0 {
+            caret += 8;
+        }
+        if flg & FLG_DICT_ID != 0 {
+            assert!(
+                !dict.is_empty(),
+                "frame needs a preset dictionary, pass --dict"
+            );
+            assert!(
+                read_u32(archive, caret) == adler32(dict),
+                "preset dictionary does not match the frame"
+            );
+            caret += 4;
+        }
+        caret += 1;
+
+        let dict = window(dict);
+        let mut frame = dict.to_vec();
+
+        loop {
+            let size = read_u32(archive, caret);
+            caret += 4;
+            if size == 0 {
+                break;
+            }
+            let length = (size & !BLOCK_UNCOMPRESSED) as usize;
+            let block = &archive[caret..caret + length];
+            caret += length;
+            if flg & FLG_BLOCK_CHECKSUM != 0 {
+                assert!(
+                    read_u32(archive, caret) == xxh32(block, 0),
+                    "block checksum mismatch"
+                );
+                caret += 4;
+            }
+            if size & BLOCK_UNCOMPRESSED != 0 {
+                frame.extend_from_slice(block);
+            } else if flg & FLG_BLOCK_INDEPENDENT != 0 {
+                let mut output = dict.to_vec();
+                decompress_block(block, &mut output);
+                frame.extend_from_slice(&output[dict.len()..]);
+            } else {
+                decompress_block(block, &mut frame);
+            }
+        }
+
+        let frame = &frame[dict.len()..];
+        result.extend_from_slice(frame);
+        if flg & FLG_CONTENT_CHECKSUM != 0 {
+            let checksum = xxh32(frame, 0);
+            assert!(
+                read_u32(archive, caret) == checksum,
+                "content checksum mismatch"
+            );
+            caret += 4;
+        }
+    }
+    return result;
+}
@@ -1,75 +1,162 @@
-pub fn lz_match(data: &Vec<u8>, pos1: usize, pos2: usize, length: usize) -> bool {
-    for i in 0..length {
-        if data[pos1 + i] != data[pos2 + i] {
-            return false;
-        }
+use crate::matchfinder::Params;
+use crate::parser::{parse, Costs, Tokens};
+
+pub struct Format {
+    pub window_size: u32,
+    pub min_length: u32,
+    pub max_length: u32,
+    pub offset_bytes: u8,
+    pub length_bytes: u8,
+}
+
+fn bytes_for(value: u32) -> u8 {
+    let mut n = 1;
+    while n < 4 && value >> (8 * n) != 0 {
+        n += 1;
     }
-    return true;
+    return n as u8;
 }
 
-pub fn lz77_encode(data: &Vec<u8>, window_size: usize) -> Vec<(u32, u8, u8)> {
-    let mut ptr = 0;
-    let mut encoded = Vec::new();
-
-    while ptr < data.len() {
-        let mut saved = None;
-        let mut l = 1;
-        while ptr + l < data.len() && l < 256 {
-            let mut found = false;
-            for offset in 1..window_size {
-                if (ptr as i32) - (offset as i32) < 0 {
-                    break;
-                }
-                if lz_match(data, ptr - offset as usize, ptr, l) {
-                    found = true;
-                    saved = Some((l, offset));
-                    l += 1;
-                    break;
-                }
-            }
-            if !found {
-                break;
-            }
+fn write_field(result: &mut Vec<u8>, value: u32, size: u8) {
+    result.extend_from_slice(&value.to_le_bytes()[..size as usize]);
+}
+
+fn read_field(data: &[u8], size: u8) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes[..size as usize].copy_from_slice(&data[..size as usize]);
+    return u32::from_le_bytes(bytes);
+}
+
+impl Format {
+    pub fn new(
+        window_size: u32,
+        min_length: u32,
+        max_length: u32,
+        offset_bytes: Option<u8>,
+        length_bytes: Option<u8>,
+    ) -> Result<Self, &'static str> {
+        if window_size < 2 {
+            return Err("window size must be at least 2");
         }
-        if let Some((l, offset)) = saved {
-            encoded.push((offset as u32, l as u8, data[ptr + l]));
-            ptr += l + 1;
-        } else {
-            encoded.push((0, 0, data[ptr]));
-            ptr += 1;
+        if min_length < 1 {
+            return Err("minimum match length must be at least 1");
+        }
+        if max_length < min_length {
+            return Err("maximum match length is below the minimum");
         }
+        let offset_bytes = offset_bytes.unwrap_or(bytes_for(window_size - 1));
+        let length_bytes = length_bytes.unwrap_or(bytes_for(max_length));
+        if !(1..=4).contains(&offset_bytes) || offset_bytes < bytes_for(window_size - 1) {
+            return Err("offset field is too narrow for the window");
+        }
+        if !(1..=4).contains(&length_bytes) || length_bytes < bytes_for(max_length) {
+            return Err("length field is too narrow for the maximum match length");
+        }
+
+        return Ok(Self {
+            window_size,
+            min_length,
+            max_length,
+            offset_bytes,
+            length_bytes,
+        });
     }
 
-    return encoded;
+    pub fn load(data: &[u8]) -> Self {
+        let read = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        return Self {
+            window_size: read(0),
+            min_length: read(4),
+            max_length: read(8),
+            offset_bytes: data[12],
+            length_bytes: data[13],
+        };
+    }
+
+    pub fn size() -> usize {
+        return 14;
+    }
+
+    pub fn dump(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        result.extend_from_slice(&self.window_size.to_le_bytes());
+        result.extend_from_slice(&self.min_length.to_le_bytes());
+        result.extend_from_slice(&self.max_length.to_le_bytes());
+        result.push(self.offset_bytes);
+        result.push(self.length_bytes);
+        return result;
+    }
+
+    pub fn token_size(&self) -> usize {
+        return (self.offset_bytes + self.length_bytes + 1) as usize;
+    }
+
+    pub fn dump_token(&self, result: &mut Vec<u8>, token: &(u32, u32, u8)) {
+        write_field(result, token.0, self.offset_bytes);
+        write_field(result, token.1, self.length_bytes);
+        result.push(token.2);
+    }
+
+    pub fn read_token(&self, data: &[u8]) -> (u32, u32, u8) {
+        let offset = read_field(data, self.offset_bytes);
+        let length = read_field(&data[self.offset_bytes as usize..], self.length_bytes);
+        return (offset, length, data[self.token_size() - 1]);
+    }
 }
 
-pub fn compress(data: &Vec<u8>) -> Vec<u8> {
-    let mut result = Vec::new();
-    let encoded = lz77_encode(data, 8192);
+pub fn lz77_encode(
+    data: &Vec<u8>,
+    dict: &[u8],
+    format: &Format,
+    params: Params,
+) -> Vec<(u32, u32, u8)> {
+    let input = [dict, data].concat();
+    let tokens = Tokens {
+        min_length: format.min_length as usize,
+        reserve: 1,
+        costs: Costs {
+            literal: format.token_size() as u32 * 8,
+            pair: format.token_size() as u32 * 8,
+        },
+    };
+    let steps = parse(
+        &input,
+        dict.len(),
+        format.window_size as usize,
+        format.max_length as usize,
+        params,
+        &tokens,
+    );
+
+    let mut ptr = dict.len();
+    let mut encoded = Vec::with_capacity(steps.len());
+    for (offset, l) in steps {
+        ptr += l as usize;
+        encoded.push((offset, l, input[ptr]));
+        ptr += 1;
+    }
+
+    return encoded;
+}
 
-    for (offset, length, byte) in &encoded {
-        let offset_bytes: [u8; 4] = unsafe { std::mem::transmute(*offset) };
-        result.push(offset_bytes[0]);
-        result.push(offset_bytes[1]);
+pub fn compress(data: &Vec<u8>, dict: &[u8], format: &Format, params: Params) -> Vec<u8> {
+    let mut result = format.dump();
+    let encoded = lz77_encode(data, dict, format, params);
 
-        result.push(*length);
-        result.push(*byte);
+    for token in &encoded {
+        format.dump_token(&mut result, token);
     }
 
     return result;
 }
 
-pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
-    let mut result = Vec::new();
-    let mut ptr: usize = 0;
-    let block_size = 4;
-    for i in 0..archive.len() / block_size {
+fn decode_tokens(tokens: &[u8], dict: &[u8], format: &Format) -> Vec<u8> {
+    let mut result = dict.to_vec();
+    let mut ptr: usize = dict.len();
+    let block_size = format.token_size();
+    for i in 0..tokens.len() / block_size {
         let b_offset = i * block_size;
-
-        let offset_bytes: [u8; 4] = [archive[b_offset + 0], archive[b_offset + 1], 0, 0];
-        let offset: u32 = unsafe { std::mem::transmute(offset_bytes) };
-        let length = archive[b_offset + 2] as usize;
-        let byte = archive[b_offset + 3];
+        let (offset, length, byte) = format.read_token(&tokens[b_offset..]);
 
         if length == 0 {
             result.push(byte);
@@ -82,5 +169,67 @@ pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
             ptr += length as usize + 1;
         }
     }
+    return result.split_off(dict.len());
+}
+
+pub fn decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let format = Format::load(archive);
+    return decode_tokens(&archive[Format::size()..], dict, &format);
+}
+
+pub fn compress_ans(
+    data: &Vec<u8>,
+    dict: &[u8],
+    format: &Format,
+    coder: ans::Coder,
+    params: Params,
+) -> Vec<u8> {
+    let mut result = format.dump();
+    let encoded = lz77_encode(data, dict, format, params);
+
+    let lanes = format.token_size();
+    let mut streams = vec![Vec::new(); lanes];
+    let mut token_bytes = Vec::with_capacity(lanes);
+    for token in &encoded {
+        token_bytes.clear();
+        format.dump_token(&mut token_bytes, token);
+        for (lane, byte) in token_bytes.iter().enumerate() {
+            streams[lane].push(*byte);
+        }
+    }
+
+    for stream in &streams {
+        let mut coded = ans::encode(stream, coder);
+        result.extend_from_slice(&(coded.len() as u32).to_le_bytes());
+        result.append(&mut coded);
+    }
+
     return result;
 }
+
+pub fn decompress_ans(archive: &Vec<u8>, dict: &[u8], coder: ans::Coder) -> Vec<u8> {
+    let format = Format::load(archive);
+    let lanes = format.token_size();
+    let mut streams = Vec::new();
+    let mut caret = Format::size();
+    for _ in 0..lanes {
+        let size = u32::from_le_bytes([
+            archive[caret],
+            archive[caret + 1],
+            archive[caret + 2],
+            archive[caret + 3],
+        ]) as usize;
+        caret += 4;
+        streams.push(ans::decode(&archive[caret..caret + size], coder));
+        caret += size;
+    }
+
+    let mut raw = Vec::with_capacity(streams[0].len() * lanes);
+    for i in 0..streams[0].len() {
+        for stream in &streams {
+            raw.push(stream[i]);
+        }
+    }
+
+    return decode_tokens(&raw, dict, &format);
+}
@@ -0,0 +1,185 @@
+use crate::canonical;
+use crate::lz77::Format;
+use crate::matchfinder::Params;
+use crate::parser::{parse, Costs, Tokens};
+use bits::{BitReader, BitWriter};
+
+const MIN_LENGTH: u32 = 3;
+const BLOCK_TOKENS: usize = 16384;
+const DIRECT_SLOTS: u32 = 16;
+
+fn bits_for(value: u32) -> u32 {
+    return 32 - value.leading_zeros();
+}
+
+fn slot(value: u32) -> u32 {
+    if value < DIRECT_SLOTS {
+        return value;
+    }
+    let n = bits_for(value);
+    return DIRECT_SLOTS + 2 * (n - 5) + ((value >> (n - 2)) & 1);
+}
+
+fn slot_base(slot: u32) -> (u32, u32) {
+    if slot < DIRECT_SLOTS {
+        return (slot, 0);
+    }
+    let n = (slot - DIRECT_SLOTS) / 2 + 5;
+    let base = (2 | ((slot - DIRECT_SLOTS) & 1)) << (n - 2);
+    return (base, n - 2);
+}
+
+fn write_slotted(writer: &mut BitWriter, codes: &[u32], lengths: &[u8], first: usize, value: u32) {
+    let s = slot(value);
+    let (base, extra) = slot_base(s);
+    let symbol = first + s as usize;
+    writer.write(codes[symbol], lengths[symbol] as u32);
+    writer.write(value - base, extra);
+}
+
+fn read_slotted(reader: &mut BitReader, symbol: u32) -> u32 {
+    let (base, extra) = slot_base(symbol);
+    return base + reader.read(extra);
+}
+
+struct Alphabets {
+    min_length: u32,
+    literals: usize,
+    offsets: usize,
+}
+
+impl Alphabets {
+    fn new(format: &Format) -> Self {
+        let min_length = std::cmp::max(format.min_length, MIN_LENGTH);
+        assert!(
+            format.max_length >= min_length,
+            "maximum match length is below the minimum LZH match"
+        );
+        return Self {
+            min_length,
+            literals: 256 + slot(format.max_length - min_length) as usize + 1,
+            offsets: slot(format.window_size - 2) as usize + 1,
+        };
+    }
+}
+
+fn write_block(writer: &mut BitWriter, tokens: &[(u32, u32)], bytes: &[u8], alphabets: &Alphabets) {
+    let mut lit_freqs = vec![0u32; alphabets.literals];
+    let mut off_freqs = vec![0u32; alphabets.offsets];
+    let mut ptr = 0;
+    for &(offset, l) in tokens {
+        if l == 0 {
+            lit_freqs[bytes[ptr] as usize] += 1;
+            ptr += 1;
+        } else {
+            lit_freqs[256 + slot(l - alphabets.min_length) as usize] += 1;
+            off_freqs[slot(offset - 1) as usize] += 1;
+            ptr += l as usize;
+        }
+    }
+
+    let lit_lengths = canonical::lengths(&lit_freqs, 15);
+    let off_lengths = canonical::lengths(&off_freqs, 15);
+    let lit_codes = canonical::codes(&lit_lengths);
+    let off_codes = canonical::codes(&off_lengths);
+    writer.write(tokens.len() as u32, 16);
+    canonical::write_lengths(writer, &lit_lengths);
+    canonical::write_lengths(writer, &off_lengths);
+
+    let mut ptr = 0;
+    for &(offset, l) in tokens {
+        if l == 0 {
+            let s = bytes[ptr] as usize;
+            writer.write(lit_codes[s], lit_lengths[s] as u32);
+            ptr += 1;
+        } else {
+            write_slotted(
+                writer,
+                &lit_codes,
+                &lit_lengths,
+                256,
+                l - alphabets.min_length,
+            );
+            write_slotted(writer, &off_codes, &off_lengths, 0, offset - 1);
+            ptr += l as usize;
+        }
+    }
+}
+
+pub fn compress(data: &Vec<u8>, dict: &[u8], format: &Format, params: Params) -> Vec<u8> {
+    let input = [dict, data].concat();
+    let mut result = format.dump();
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    let alphabets = Alphabets::new(format);
+    let tokens = Tokens {
+        min_length: alphabets.min_length as usize,
+        reserve: 0,
+        costs: Costs {
+            literal: 9,
+            pair: 20,
+        },
+    };
+    let steps = parse(
+        &input,
+        dict.len(),
+        format.window_size as usize,
+        format.max_length as usize,
+        params,
+        &tokens,
+    );
+
+    let mut writer = BitWriter::new();
+    let mut ptr = dict.len();
+    for block in steps.chunks(BLOCK_TOKENS) {
+        let size: usize = block
+            .iter()
+            .map(|&(_, l)| std::cmp::max(l, 1) as usize)
+            .sum();
+        write_block(&mut writer, block, &input[ptr..ptr + size], &alphabets);
+        ptr += size;
+    }
+    result.append(&mut writer.finish());
+
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let format = Format::load(archive);
+    let alphabets = Alphabets::new(&format);
+    let caret = Format::size();
+    let length = u32::from_le_bytes([
+        archive[caret],
+        archive[caret + 1],
+        archive[caret + 2],
+        archive[caret + 3],
+    ]) as usize;
+
+    let mut result = dict.to_vec();
+    let mut reader = BitReader::new(&archive[caret + 4..]);
+    while result.len() < dict.len() + length {
+        let count = reader.read(16);
+        let lit =
+            canonical::Decoder::new(&canonical::read_lengths(&mut reader, alphabets.literals));
+        let off = canonical::Decoder::new(&canonical::read_lengths(&mut reader, alphabets.offsets));
+        for _ in 0..count {
+            let s = lit.decode(&mut reader) as u32;
+            if s < 256 {
+                result.push(s as u8);
+                continue;
+            }
+            let l = read_slotted(&mut reader, s - 256) + alphabets.min_length;
+            let symbol = off.decode(&mut reader) as u32;
+            let offset = read_slotted(&mut reader, symbol) + 1;
+            assert!(
+                offset as usize <= result.len(),
+                "offset points before the start"
+            );
+            let start = result.len() - offset as usize;
+            for j in 0..l as usize {
+                result.push(result[start + j]);
+            }
+        }
+    }
+
+    return result.split_off(dict.len());
+}
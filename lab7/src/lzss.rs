@@ -0,0 +1,132 @@
+use crate::lz77::Format;
+use crate::matchfinder::Params;
+use crate::parser::{parse, Costs, Tokens};
+
+fn bits_for(value: u32) -> u32 {
+    return 32 - value.leading_zeros();
+}
+
+struct Layout {
+    length_bits: u32,
+    pair_bytes: usize,
+    min_length: u32,
+}
+
+impl Layout {
+    fn new(format: &Format) -> Self {
+        let offset_bits = bits_for(format.window_size - 1);
+        let length_bits = bits_for(format.max_length - format.min_length);
+        let pair_bytes = ((offset_bits + length_bits + 7) / 8) as usize;
+        let break_even = (pair_bytes as u32 * 8 + 1) / 9 + 1;
+        assert!(
+            pair_bytes <= 8,
+            "window and match length do not fit into a pair"
+        );
+        assert!(
+            format.max_length >= break_even,
+            "maximum match length is below the break-even length"
+        );
+
+        return Self {
+            length_bits,
+            pair_bytes,
+            min_length: std::cmp::max(format.min_length, break_even),
+        };
+    }
+
+    fn dump_pair(&self, result: &mut Vec<u8>, offset: u32, length: u32, base: u32) {
+        let value = (offset as u64) << self.length_bits | (length - base) as u64;
+        result.extend_from_slice(&value.to_le_bytes()[..self.pair_bytes]);
+    }
+
+    fn read_pair(&self, data: &[u8], base: u32) -> (usize, usize) {
+        let mut bytes = [0u8; 8];
+        bytes[..self.pair_bytes].copy_from_slice(&data[..self.pair_bytes]);
+        let value = u64::from_le_bytes(bytes);
+        let offset = value >> self.length_bits;
+        let length = (value & ((1 << self.length_bits) - 1)) as u32 + base;
+        return (offset as usize, length as usize);
+    }
+}
+
+pub fn compress(data: &Vec<u8>, dict: &[u8], format: &Format, params: Params) -> Vec<u8> {
+    let input = [dict, data].concat();
+    let mut result = format.dump();
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    let layout = Layout::new(format);
+    let tokens = Tokens {
+        min_length: layout.min_length as usize,
+        reserve: 0,
+        costs: Costs {
+            literal: 9,
+            pair: layout.pair_bytes as u32 * 8 + 1,
+        },
+    };
+    let steps = parse(
+        &input,
+        dict.len(),
+        format.window_size as usize,
+        format.max_length as usize,
+        params,
+        &tokens,
+    );
+
+    let mut flags_pos = 0;
+    let mut ptr = dict.len();
+    for (i, (offset, l)) in steps.into_iter().enumerate() {
+        if i % 8 == 0 {
+            flags_pos = result.len();
+            result.push(0);
+        }
+
+        if l > 0 {
+            result[flags_pos] |= 1 << (i % 8);
+            layout.dump_pair(&mut result, offset, l, format.min_length);
+            ptr += l as usize;
+        } else {
+            result.push(input[ptr]);
+            ptr += 1;
+        }
+    }
+
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    let format = Format::load(archive);
+    let layout = Layout::new(&format);
+    let mut caret = Format::size();
+    let length = u32::from_le_bytes([
+        archive[caret],
+        archive[caret + 1],
+        archive[caret + 2],
+        archive[caret + 3],
+    ]) as usize;
+    caret += 4;
+
+    let mut result = dict.to_vec();
+    let mut flags = 0;
+    let mut count = 8;
+    while result.len() < dict.len() + length {
+        if count == 8 {
+            flags = archive[caret];
+            caret += 1;
+            count = 0;
+        }
+
+        if flags & (1 << count) != 0 {
+            let (offset, l) = layout.read_pair(&archive[caret..], format.min_length);
+            caret += layout.pair_bytes;
+            let start = result.len() - offset;
+            for j in 0..l {
+                result.push(result[start + j]);
+            }
+        } else {
+            result.push(archive[caret]);
+            caret += 1;
+        }
+        count += 1;
+    }
+
+    return result.split_off(dict.len());
+}
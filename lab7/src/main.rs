@@ -1,9 +1,20 @@
+mod canonical;
+mod deflate;
+mod gzip;
+mod lz4;
 mod lz77;
-use clap::Parser;
+mod lzh;
+mod lzss;
+mod matchfinder;
+mod parser;
+mod stream;
+use clap::{CommandFactory, Parser, ValueEnum};
 use std::fs::File;
-use std::io::{Error, ErrorKind, Read, Write};
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
 use std::path::PathBuf;
 
+const DICT_FLAG: u8 = 0x80;
+
 #[derive(Parser)]
 struct Cli {
     #[arg(short)]
@@ -17,13 +28,175 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     decompress: bool,
+
+    #[arg(long, value_enum, default_value_t = Method::Lz77)]
+    method: Method,
+
+    #[arg(long, value_enum, default_value_t = Coder::Raw)]
+    coder: Coder,
+
+    #[arg(long, value_enum, default_value_t = matchfinder::Finder::HashChain)]
+    finder: matchfinder::Finder,
+
+    #[arg(long, default_value_t = 256)]
+    chain_depth: usize,
+
+    #[arg(long, default_value_t = 255)]
+    nice_length: usize,
+
+    #[arg(long, value_enum, default_value_t = parser::Parser::Greedy)]
+    parser: parser::Parser,
+
+    #[arg(long, default_value_t = 8192, value_parser = clap::value_parser!(u32).range(2..))]
+    window_size: u32,
+
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    min_length: u32,
+
+    #[arg(long, default_value_t = 255, value_parser = clap::value_parser!(u32).range(1..))]
+    max_length: u32,
+
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+    offset_bytes: Option<u8>,
+
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+    length_bytes: Option<u8>,
+
+    #[arg(long)]
+    dict: Option<PathBuf>,
+
+    #[arg(long, default_value_t = false, conflicts_with = "dict")]
+    stream: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Method {
+    Lz77,
+    Lzss,
+    Lzh,
+    Deflate,
+    Gzip,
+    Zlib,
+    Lz4,
+    Lz4Frame,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Coder {
+    Raw,
+    Rans,
+    Tans,
+}
+
+fn decompress(archive: &Vec<u8>, dict: &[u8]) -> Vec<u8> {
+    if gzip::is_gzip(archive) {
+        return gzip::gzip_decompress(archive);
+    }
+    if gzip::is_zlib(archive) {
+        return gzip::zlib_decompress(archive, dict);
+    }
+    if lz4::is_frame(archive) {
+        return lz4::decompress_frame(archive, dict);
+    }
+
+    let mut caret = 1;
+    let dict = if archive[0] & DICT_FLAG != 0 {
+        let id = u32::from_le_bytes([archive[1], archive[2], archive[3], archive[4]]);
+        assert!(
+            !dict.is_empty(),
+            "archive needs a preset dictionary, pass --dict"
+        );
+        assert!(
+            id == checksum::adler32(dict),
+            "preset dictionary does not match the archive"
+        );
+        caret += 4;
+        dict
+    } else {
+        &[]
+    };
+    let data = archive[caret..].to_vec();
+    match archive[0] & !DICT_FLAG {
+        0 => lz77::decompress(&data, dict),
+        1 => lz77::decompress_ans(&data, dict, ans::Coder::Rans),
+        2 => lz77::decompress_ans(&data, dict, ans::Coder::Tans),
+        3 => lzss::decompress(&data, dict),
+        4 => deflate::decompress(&data, dict),
+        5 => lzh::decompress(&data, dict),
+        _ => lz4::decompress(&data, dict),
+    }
+}
+
+fn params(cli: &Cli) -> matchfinder::Params {
+    return matchfinder::Params {
+        finder: cli.finder,
+        chain_depth: cli.chain_depth,
+        nice_length: cli.nice_length,
+        parser: cli.parser,
+    };
+}
+
+fn format(cli: &Cli) -> lz77::Format {
+    let format = lz77::Format::new(
+        cli.window_size,
+        cli.min_length,
+        cli.max_length,
+        cli.offset_bytes,
+        cli.length_bytes,
+    );
+    match format {
+        Ok(format) => return format,
+        Err(message) => Cli::command()
+            .error(clap::error::ErrorKind::ValueValidation, message)
+            .exit(),
+    }
+}
+
+fn compress(data: &Vec<u8>, dict: &[u8], cli: &Cli) -> Vec<u8> {
+    let params = params(cli);
+    let format = format(cli);
+    let (method, mut archive) = match (cli.method, cli.coder) {
+        (Method::Lz77, Coder::Raw) => (0, lz77::compress(data, dict, &format, params)),
+        (Method::Lz77, Coder::Rans) => (
+            1,
+            lz77::compress_ans(data, dict, &format, ans::Coder::Rans, params),
+        ),
+        (Method::Lz77, Coder::Tans) => (
+            2,
+            lz77::compress_ans(data, dict, &format, ans::Coder::Tans, params),
+        ),
+        (Method::Lzss, Coder::Raw) => (3, lzss::compress(data, dict, &format, params)),
+        (Method::Deflate, Coder::Raw) => (4, deflate::compress(data, dict, params)),
+        (Method::Lzh, Coder::Raw) => (5, lzh::compress(data, dict, &format, params)),
+        (Method::Lz4, Coder::Raw) => (6, lz4::compress(data, dict)),
+        (Method::Lz4Frame, Coder::Raw) => return lz4::compress_frame(data, dict),
+        (Method::Gzip, Coder::Raw) => return gzip::gzip_compress(data, params),
+        (Method::Zlib, Coder::Raw) => return gzip::zlib_compress(data, dict, params),
+        (_, _) => unreachable!("check_args rejects --coder for this method"),
+    };
+    let mut result = vec![method];
+    if !dict.is_empty() {
+        result[0] |= DICT_FLAG;
+        result.extend_from_slice(&checksum::adler32(dict).to_le_bytes());
+    }
+    result.append(&mut archive);
+    return result;
+}
+
+fn read_dict(cli: &Cli) -> Result<Vec<u8>, Error> {
+    let mut dict = Vec::new();
+    if let Some(path) = &cli.dict {
+        let mut dict_f = File::open(path.to_str().unwrap())?;
+        dict_f.read_to_end(&mut dict)?;
+    }
+    return Ok(dict);
 }
 
 fn run_decompressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut archive = Vec::new();
     input_f.read_to_end(&mut archive)?;
-    let data = lz77::decompress(&archive);
+    let data = decompress(&archive, &read_dict(cli)?);
 
     let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
     output_f.write_all(&data)?;
@@ -35,7 +208,7 @@ fn run_compressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut data = Vec::new();
     input_f.read_to_end(&mut data)?;
-    let archive = lz77::compress(&data);
+    let archive = compress(&data, &read_dict(cli)?, cli);
 
     let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
     output_f.write_all(&archive)?;
@@ -43,10 +216,56 @@ fn run_compressor(cli: &Cli) -> Result<(), Error> {
     return Ok(());
 }
 
+fn run_stream_decompressor(cli: &Cli) -> Result<(), Error> {
+    let mut input_f = BufReader::new(File::open(cli.input_file.to_str().unwrap())?);
+    let mut method = [0u8];
+    input_f.read_exact(&mut method)?;
+    assert!(
+        method[0] == 0,
+        "only raw LZ77 archives can be decompressed as a stream"
+    );
+    let mut output_f = BufWriter::new(File::create(cli.output_file.to_str().unwrap())?);
+    return stream::decompress(&mut input_f, &mut output_f);
+}
+
+fn run_stream_compressor(cli: &Cli) -> Result<(), Error> {
+    let mut input_f = BufReader::new(File::open(cli.input_file.to_str().unwrap())?);
+    let mut output_f = BufWriter::new(File::create(cli.output_file.to_str().unwrap())?);
+    output_f.write_all(&[0])?;
+    return stream::compress(&mut input_f, &mut output_f, &format(cli), params(cli));
+}
+
+// Checks that depend on the values of several arguments, so clap cannot
+// express them as attributes.
+fn check_args(cli: &Cli) {
+    let message = if cli.stream && (cli.method != Method::Lz77 || cli.coder != Coder::Raw) {
+        Some("streaming is only supported for raw LZ77")
+    } else if cli.method != Method::Lz77 && cli.coder != Coder::Raw {
+        Some("this method writes its own bit-packed tokens, use --coder raw")
+    } else if cli.method == Method::Gzip && cli.dict.is_some() {
+        Some("gzip has no preset dictionaries, use zlib")
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        Cli::command()
+            .error(clap::error::ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+    if !cli.decompress {
+        format(cli);
+    }
+}
+
 fn main() -> std::io::Result<()> {
     let cli = Cli::parse();
+    check_args(&cli);
 
-    let result = if cli.decompress {
+    let result = if cli.stream && cli.decompress {
+        run_stream_decompressor(&cli)
+    } else if cli.stream {
+        run_stream_compressor(&cli)
+    } else if cli.decompress {
         run_decompressor(&cli)
     } else {
         run_compressor(&cli)
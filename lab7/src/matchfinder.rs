@@ -0,0 +1,153 @@
+use crate::parser::Parser;
+use clap::ValueEnum;
+
+const HASH_BITS: u32 = 16;
+const NONE: usize = usize::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Finder {
+    Brute,
+    HashChain,
+}
+
+#[derive(Clone, Copy)]
+pub struct Params {
+    pub finder: Finder,
+    pub chain_depth: usize,
+    pub nice_length: usize,
+    pub parser: Parser,
+}
+
+fn hash3(data: &[u8], pos: usize) -> usize {
+    let key = (data[pos] as u32) << 16 | (data[pos + 1] as u32) << 8 | data[pos + 2] as u32;
+    return (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize;
+}
+
+fn hash2(data: &[u8], pos: usize) -> usize {
+    return (data[pos] as usize) << 8 | data[pos + 1] as usize;
+}
+
+pub fn match_length(data: &[u8], pos1: usize, pos2: usize, limit: usize) -> usize {
+    let mut l = 0;
+    while l < limit && data[pos1 + l] == data[pos2 + l] {
+        l += 1;
+    }
+    return l;
+}
+
+pub struct MatchFinder<'a> {
+    data: &'a [u8],
+    window_size: usize,
+    max_length: usize,
+    params: Params,
+    head: Vec<usize>,
+    prev: Vec<usize>,
+    last1: Vec<usize>,
+    last2: Vec<usize>,
+    inserted: usize,
+}
+
+impl<'a> MatchFinder<'a> {
+    pub fn new(data: &'a [u8], window_size: usize, max_length: usize, params: Params) -> Self {
+        let hashed = params.finder == Finder::HashChain;
+        Self {
+            data,
+            window_size,
+            max_length,
+            params,
+            head: vec![NONE; if hashed { 1 << HASH_BITS } else { 0 }],
+            prev: vec![NONE; if hashed { window_size } else { 0 }],
+            last1: vec![NONE; if hashed { 1 << 8 } else { 0 }],
+            last2: vec![NONE; if hashed { 1 << 16 } else { 0 }],
+            inserted: 0,
+        }
+    }
+
+    fn insert(&mut self, pos: usize) {
+        let data = self.data;
+        self.last1[data[pos] as usize] = pos;
+        if pos + 1 < data.len() {
+            self.last2[hash2(data, pos)] = pos;
+        }
+        if pos + 2 < data.len() {
+            let h = hash3(data, pos);
+            self.prev[pos % self.window_size] = self.head[h];
+            self.head[h] = pos;
+        }
+    }
+
+    fn in_window(&self, ptr: usize, candidate: usize) -> bool {
+        return candidate != NONE && ptr - candidate < self.window_size;
+    }
+
+    fn find_brute(&self, ptr: usize, limit: usize) -> Option<(usize, usize)> {
+        let mut best = None;
+        let mut best_length = 0;
+        for offset in 1..std::cmp::min(self.window_size, ptr + 1) {
+            let l = match_length(self.data, ptr - offset, ptr, limit);
+            if l > best_length {
+                best = Some((offset, l));
+                best_length = l;
+                if l == limit {
+                    break;
+                }
+            }
+        }
+        return best;
+    }
+
+    fn find_hash_chain(&mut self, ptr: usize, limit: usize) -> Option<(usize, usize)> {
+        while self.inserted < ptr {
+            self.insert(self.inserted);
+            self.inserted += 1;
+        }
+
+        let data = self.data;
+        let mut best = None;
+        let mut best_length = 0;
+        if limit >= 3 {
+            let mut candidate = self.head[hash3(data, ptr)];
+            let mut depth = 0;
+            while self.in_window(ptr, candidate) && depth < self.params.chain_depth {
+                let l = match_length(data, candidate, ptr, limit);
+                if l > best_length {
+                    best = Some((ptr - candidate, l));
+                    best_length = l;
+                    if l >= self.params.nice_length || l == limit {
+                        break;
+                    }
+                }
+                candidate = self.prev[candidate % self.window_size];
+                depth += 1;
+            }
+        }
+
+        let mut short = Vec::new();
+        if limit >= 2 {
+            short.push(self.last2[hash2(data, ptr)]);
+        }
+        short.push(self.last1[data[ptr] as usize]);
+        for candidate in short {
+            if best_length < 2 && self.in_window(ptr, candidate) {
+                let l = match_length(data, candidate, ptr, limit);
+                if l > best_length {
+                    best = Some((ptr - candidate, l));
+                    best_length = l;
+                }
+            }
+        }
+
+        return best;
+    }
+
+    pub fn find_limited(&mut self, ptr: usize, limit: usize) -> Option<(usize, usize)> {
+        let limit = std::cmp::min(self.max_length, limit);
+        if limit == 0 {
+            return None;
+        }
+        match self.params.finder {
+            Finder::Brute => self.find_brute(ptr, limit),
+            Finder::HashChain => self.find_hash_chain(ptr, limit),
+        }
+    }
+}
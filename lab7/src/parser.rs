@@ -0,0 +1,147 @@
+use crate::matchfinder::{MatchFinder, Params};
+use clap::ValueEnum;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Parser {
+    Greedy,
+    Lazy1,
+    Lazy2,
+    Optimal,
+}
+
+pub struct Costs {
+    pub literal: u32,
+    pub pair: u32,
+}
+
+pub struct Tokens {
+    pub min_length: usize,
+    pub reserve: usize,
+    pub costs: Costs,
+}
+
+struct Lookahead<'a> {
+    finder: MatchFinder<'a>,
+    size: usize,
+    reserve: usize,
+    min_length: usize,
+    cache: VecDeque<(usize, usize, usize)>,
+}
+
+impl<'a> Lookahead<'a> {
+    fn at(&mut self, pos: usize) -> (usize, usize) {
+        if let Some(&(_, offset, l)) = self.cache.iter().find(|e| e.0 == pos) {
+            return (offset, l);
+        }
+
+        let mut found = (0, 0);
+        if pos + self.reserve < self.size {
+            if let Some((offset, l)) = self
+                .finder
+                .find_limited(pos, self.size - pos - self.reserve)
+            {
+                if l >= self.min_length {
+                    found = (offset, l);
+                }
+            }
+        }
+        if self.cache.len() == 3 {
+            self.cache.pop_front();
+        }
+        self.cache.push_back((pos, found.0, found.1));
+        return found;
+    }
+}
+
+fn lazy(look: &mut Lookahead, start: usize, size: usize, steps: usize) -> Vec<(u32, u32)> {
+    let mut result = Vec::new();
+    let mut ptr = start;
+    while ptr < size {
+        let (offset, l) = look.at(ptr);
+        let mut defer = false;
+        if l > 0 {
+            for d in 1..=steps {
+                if look.at(ptr + d).1 > l + d - 1 {
+                    defer = true;
+                    break;
+                }
+            }
+        }
+
+        if l == 0 || defer {
+            result.push((0, 0));
+            ptr += 1;
+        } else {
+            result.push((offset as u32, l as u32));
+            ptr += l + look.reserve;
+        }
+    }
+    return result;
+}
+
+fn optimal(look: &mut Lookahead, start: usize, size: usize, costs: &Costs) -> Vec<(u32, u32)> {
+    let mut price = vec![u64::MAX; size + 1];
+    let mut choice = vec![(0usize, 0u32, 0u32); size + 1];
+    price[start] = 0;
+    for ptr in start..size {
+        let literal = price[ptr] + costs.literal as u64;
+        if literal < price[ptr + 1] {
+            price[ptr + 1] = literal;
+            choice[ptr + 1] = (ptr, 0, 0);
+        }
+
+        let (offset, l) = look.at(ptr);
+        let pair = price[ptr] + costs.pair as u64;
+        for length in look.min_length..=l {
+            let end = ptr + length + look.reserve;
+            if pair < price[end] {
+                price[end] = pair;
+                choice[end] = (ptr, offset as u32, length as u32);
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut ptr = size;
+    while ptr > start {
+        let (from, offset, length) = choice[ptr];
+        result.push((offset, length));
+        ptr = from;
+    }
+    result.reverse();
+    return result;
+}
+
+pub fn parse(
+    data: &[u8],
+    start: usize,
+    window_size: usize,
+    max_length: usize,
+    params: Params,
+    tokens: &Tokens,
+) -> Vec<(u32, u32)> {
+    let mut look = Lookahead {
+        finder: MatchFinder::new(data, window_size, max_length, params),
+        size: data.len(),
+        reserve: tokens.reserve,
+        min_length: std::cmp::max(tokens.min_length, 1),
+        cache: VecDeque::new(),
+    };
+    // Deferring a match can save something when a literal is cheaper than a
+    // pair, or when matches shorter than min_length are ruled out: then the
+    // tail of a long match is not always a match itself, and a later, longer
+    // match can save a token. With equal prices and every tail available
+    // greedy-longest already gives the fewest tokens.
+    let steps = if tokens.costs.literal < tokens.costs.pair || tokens.min_length > 1 {
+        2
+    } else {
+        0
+    };
+    match params.parser {
+        Parser::Greedy => lazy(&mut look, start, data.len(), 0),
+        Parser::Lazy1 => lazy(&mut look, start, data.len(), std::cmp::min(steps, 1)),
+        Parser::Lazy2 => lazy(&mut look, start, data.len(), steps),
+        Parser::Optimal => optimal(&mut look, start, data.len(), &tokens.costs),
+    }
+}
@@ -0,0 +1,247 @@
+use crate::lz77::Format;
+use crate::matchfinder::Params;
+use std::io::{Error, ErrorKind, Read, Write};
+
+const HASH_BITS: u32 = 16;
+const NONE: u64 = u64::MAX;
+const FLUSH_SIZE: usize = 1 << 16;
+
+struct Ring {
+    data: Vec<u8>,
+    mask: u64,
+    filled: u64,
+    eof: bool,
+}
+
+impl Ring {
+    fn new(size: u64) -> Self {
+        let size = size.next_power_of_two();
+        Self {
+            data: vec![0; size as usize],
+            mask: size - 1,
+            filled: 0,
+            eof: false,
+        }
+    }
+
+    fn at(&self, pos: u64) -> u8 {
+        return self.data[(pos & self.mask) as usize];
+    }
+
+    fn fill<R: Read>(&mut self, input: &mut R, limit: u64) -> Result<(), Error> {
+        while !self.eof && self.filled < limit {
+            let start = (self.filled & self.mask) as usize;
+            let end = std::cmp::min(self.data.len() as u64, start as u64 + limit - self.filled);
+            match input.read(&mut self.data[start..end as usize]) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.filled += n as u64,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        return Ok(());
+    }
+
+    fn match_length(&self, pos1: u64, pos2: u64, limit: u64) -> u64 {
+        let mut l = 0;
+        while l < limit && self.at(pos1 + l) == self.at(pos2 + l) {
+            l += 1;
+        }
+        return l;
+    }
+}
+
+struct Finder {
+    window_size: u64,
+    params: Params,
+    head: Vec<u64>,
+    prev: Vec<u64>,
+    prev_mask: u64,
+    last1: Vec<u64>,
+    last2: Vec<u64>,
+    inserted: u64,
+}
+
+impl Finder {
+    fn new(window_size: u64, params: Params) -> Self {
+        let prev_size = window_size.next_power_of_two();
+        Self {
+            window_size,
+            params,
+            head: vec![NONE; 1 << HASH_BITS],
+            prev: vec![NONE; prev_size as usize],
+            prev_mask: prev_size - 1,
+            last1: vec![NONE; 1 << 8],
+            last2: vec![NONE; 1 << 16],
+            inserted: 0,
+        }
+    }
+
+    fn hash3(ring: &Ring, pos: u64) -> usize {
+        let key =
+            (ring.at(pos) as u32) << 16 | (ring.at(pos + 1) as u32) << 8 | ring.at(pos + 2) as u32;
+        return (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize;
+    }
+
+    fn hash2(ring: &Ring, pos: u64) -> usize {
+        return (ring.at(pos) as usize) << 8 | ring.at(pos + 1) as usize;
+    }
+
+    fn in_window(&self, ptr: u64, candidate: u64) -> bool {
+        return candidate != NONE && ptr - candidate < self.window_size;
+    }
+
+    fn find(&mut self, ring: &Ring, ptr: u64, limit: u64) -> Option<(u64, u64)> {
+        while self.inserted < ptr {
+            let pos = self.inserted;
+            self.last1[ring.at(pos) as usize] = pos;
+            self.last2[Self::hash2(ring, pos)] = pos;
+            let h = Self::hash3(ring, pos);
+            self.prev[(pos & self.prev_mask) as usize] = self.head[h];
+            self.head[h] = pos;
+            self.inserted += 1;
+        }
+
+        let mut best = None;
+        let mut best_length = 0;
+        if limit >= 3 {
+            let mut candidate = self.head[Self::hash3(ring, ptr)];
+            let mut depth = 0;
+            while self.in_window(ptr, candidate) && depth < self.params.chain_depth {
+                let l = ring.match_length(candidate, ptr, limit);
+                if l > best_length {
+                    best = Some((ptr - candidate, l));
+                    best_length = l;
+                    if l >= self.params.nice_length as u64 || l == limit {
+                        break;
+                    }
+                }
+                candidate = self.prev[(candidate & self.prev_mask) as usize];
+                depth += 1;
+            }
+        }
+
+        let mut short = Vec::with_capacity(2);
+        if limit >= 2 {
+            short.push(self.last2[Self::hash2(ring, ptr)]);
+        }
+        short.push(self.last1[ring.at(ptr) as usize]);
+        for candidate in short {
+            if best_length < 2 && self.in_window(ptr, candidate) {
+                let l = ring.match_length(candidate, ptr, limit);
+                if l > best_length {
+                    best = Some((ptr - candidate, l));
+                    best_length = l;
+                }
+            }
+        }
+
+        return best;
+    }
+}
+
+pub fn compress<R: Read, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    format: &Format,
+    params: Params,
+) -> Result<(), Error> {
+    output.write_all(&format.dump())?;
+
+    let window_size = format.window_size as u64;
+    let max_length = format.max_length as u64;
+    let mut ring = Ring::new(window_size + max_length + 1);
+    let capacity = ring.data.len() as u64 - window_size;
+    let mut finder = Finder::new(window_size, params);
+    let mut buffer = Vec::with_capacity(FLUSH_SIZE + format.token_size());
+
+    let mut ptr = 0u64;
+    loop {
+        if ring.filled - ptr <= max_length + 2 {
+            ring.fill(input, ptr + capacity)?;
+        }
+        if ptr == ring.filled {
+            break;
+        }
+
+        // Lookahead past the next byte is only hashed once it has been read,
+        // so the finder never inserts a position that lacks three bytes.
+        let lookahead = ring.filled - ptr - 1;
+        let limit = std::cmp::min(max_length, lookahead);
+        let found = if lookahead >= 2 {
+            finder.find(&ring, ptr, limit)
+        } else {
+            None
+        };
+        let token = match found {
+            Some((offset, l)) if l >= format.min_length as u64 => {
+                (offset as u32, l as u32, ring.at(ptr + l))
+            }
+            _ => (0, 0, ring.at(ptr)),
+        };
+        format.dump_token(&mut buffer, &token);
+        ptr += token.1 as u64 + 1;
+
+        if buffer.len() >= FLUSH_SIZE {
+            output.write_all(&buffer)?;
+            buffer.clear();
+        }
+    }
+
+    output.write_all(&buffer)?;
+    return output.flush();
+}
+
+pub fn decompress<R: Read, W: Write>(input: &mut R, output: &mut W) -> Result<(), Error> {
+    let mut header = vec![0; Format::size()];
+    input.read_exact(&mut header)?;
+    let format = Format::load(&header);
+    let window_size = format.window_size as u64;
+    let mut ring = Ring::new(window_size);
+    let mut token = vec![0; format.token_size()];
+    let mut buffer = Vec::with_capacity(FLUSH_SIZE + format.max_length as usize + 1);
+
+    let mut pos = 0u64;
+    loop {
+        let mut read = 0;
+        while read < token.len() {
+            match input.read(&mut token[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        if read == 0 {
+            break;
+        }
+        assert!(
+            read == token.len(),
+            "truncated token at the end of the stream"
+        );
+
+        let (offset, length, byte) = format.read_token(&token);
+        let offset = offset as u64;
+        assert!(
+            length == 0 || (offset > 0 && offset <= pos && offset < window_size),
+            "offset points outside the window"
+        );
+        for _ in 0..length {
+            let b = ring.at(pos - offset);
+            ring.data[(pos & ring.mask) as usize] = b;
+            buffer.push(b);
+            pos += 1;
+        }
+        ring.data[(pos & ring.mask) as usize] = byte;
+        buffer.push(byte);
+        pos += 1;
+
+        if buffer.len() >= FLUSH_SIZE {
+            output.write_all(&buffer)?;
+            buffer.clear();
+        }
+    }
+
+    output.write_all(&buffer)?;
+    return output.flush();
+}
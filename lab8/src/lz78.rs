@@ -1,109 +1,487 @@
-const DICT_SIZE: usize = 4096;
+use bits::{BitReader, BitWriter};
+use checksum::adler32;
+use clap::ValueEnum;
+use rangecoder::{Decoder, Encoder, Model};
+use std::collections::{BTreeSet, HashMap};
 
-pub fn lz_match(data: &Vec<u8>, pos1: usize, pos2: usize, length: usize) -> bool {
-    for i in 0..length {
-        if data[pos1 + i] != data[pos2 + i] {
-            return false;
+const INDEX_CONTEXTS: usize = 33 * 256;
+const HIGH_BITS: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Policy {
+    Freeze,
+    Reset,
+    Adaptive,
+    Lru,
+    Lfu,
+}
+
+impl Policy {
+    fn from_u8(x: u8) -> Policy {
+        match x {
+            0 => Policy::Freeze,
+            1 => Policy::Reset,
+            2 => Policy::Adaptive,
+            3 => Policy::Lru,
+            _ => Policy::Lfu,
         }
     }
-    return true;
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Policy::Freeze => 0,
+            Policy::Reset => 1,
+            Policy::Adaptive => 2,
+            Policy::Lru => 3,
+            Policy::Lfu => 4,
+        }
+    }
+
+    fn evicts(&self) -> bool {
+        return *self == Policy::Lru || *self == Policy::Lfu;
+    }
+
+    fn clears(&self) -> bool {
+        return *self == Policy::Reset || *self == Policy::Adaptive;
+    }
 }
 
-fn lz78_encode(data: &Vec<u8>) -> Vec<(u32, u8)> {
-    let mut encoded = Vec::new();
-    let mut dict = Vec::new();
-    let mut count = 1;
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Coder {
+    Raw,
+    Adaptive,
+}
 
-    let mut ptr = 0;
-    while ptr < data.len() {
-        let mut saved = None;
-        let mut l = 1;
-        while ptr + l < data.len() && l < 256 {
-            let mut found = false;
-            for i in (0..dict.len()).rev() {
-                let (pos1, l1, val) = dict[i];
-                if l != l1 {
-                    continue;
-                }
-                if lz_match(data, pos1, ptr, l) {
-                    found = true;
-                    saved = Some((pos1, l, val));
-                    l += 1;
-                    break;
+impl Coder {
+    fn from_u8(x: u8) -> Coder {
+        if x == 0 {
+            Coder::Raw
+        } else {
+            Coder::Adaptive
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Coder::Raw => 0,
+            Coder::Adaptive => 1,
+        }
+    }
+}
+
+pub struct Params {
+    pub policy: Policy,
+    pub check_interval: u32,
+    pub dict_size: u32,
+    pub coder: Coder,
+}
+
+impl Params {
+    fn load(data: &[u8]) -> Self {
+        let read = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        return Self {
+            policy: Policy::from_u8(data[0]),
+            check_interval: read(1),
+            dict_size: read(5),
+            coder: Coder::from_u8(data[9]),
+        };
+    }
+
+    fn size() -> usize {
+        return 10;
+    }
+
+    fn dump(&self) -> Vec<u8> {
+        let mut result = vec![self.policy.to_u8()];
+        result.extend_from_slice(&self.check_interval.to_le_bytes());
+        result.extend_from_slice(&self.dict_size.to_le_bytes());
+        result.push(self.coder.to_u8());
+        return result;
+    }
+}
+
+struct Entry {
+    parent: u32,
+    byte: u8,
+    pos: usize,
+    length: usize,
+    children: u32,
+    last_use: u64,
+    uses: u64,
+}
+
+// Phrases form a trie: entry 0 is the empty phrase and every other entry is
+// its parent plus one byte. A phrase cut short at the end of the input may be
+// added twice, in which case the edge points to the newest copy. LRU and LFU
+// only ever replace leaves, so no surviving phrase loses its prefix.
+struct Dictionary {
+    policy: Policy,
+    size: usize,
+    edges: HashMap<(u32, u8), u32>,
+    entries: Vec<Entry>,
+    leaves: BTreeSet<(u64, u64, u32)>,
+    clock: u64,
+}
+
+impl Dictionary {
+    fn new(params: &Params) -> Self {
+        assert!(params.dict_size >= 1, "dictionary size must be at least 1");
+        let mut dict = Self {
+            policy: params.policy,
+            size: params.dict_size as usize,
+            edges: HashMap::new(),
+            entries: Vec::new(),
+            leaves: BTreeSet::new(),
+            clock: 0,
+        };
+        dict.reset();
+        return dict;
+    }
+
+    fn reset(&mut self) {
+        self.edges.clear();
+        self.leaves.clear();
+        self.entries.clear();
+        self.entries.push(Entry {
+            parent: 0,
+            byte: 0,
+            pos: 0,
+            length: 0,
+            children: 0,
+            last_use: 0,
+            uses: 0,
+        });
+    }
+
+    fn full(&self) -> bool {
+        return self.entries.len() > self.size;
+    }
+
+    // Indices run up to the newest phrase; policies that reset the
+    // dictionary also need the next free index, which signals a clear.
+    fn width(&self) -> u32 {
+        let largest = self.entries.len() - 1 + self.policy.clears() as usize;
+        return usize::BITS - largest.leading_zeros();
+    }
+
+    fn clear_code(&self) -> u32 {
+        return self.entries.len() as u32;
+    }
+
+    fn child(&self, node: u32, byte: u8) -> Option<u32> {
+        return self.edges.get(&(node, byte)).copied();
+    }
+
+    fn key(&self, index: u32) -> (u64, u64, u32) {
+        let entry = &self.entries[index as usize];
+        match self.policy {
+            Policy::Lfu => (entry.uses, entry.last_use, index),
+            _ => (entry.last_use, 0, index),
+        }
+    }
+
+    fn is_leaf(&self, index: u32) -> bool {
+        return self.policy.evicts() && index != 0 && self.entries[index as usize].children == 0;
+    }
+
+    fn touch(&mut self, index: u32) {
+        self.clock += 1;
+        let leaf = self.is_leaf(index);
+        if leaf {
+            self.leaves.remove(&self.key(index));
+        }
+        let entry = &mut self.entries[index as usize];
+        entry.last_use = self.clock;
+        entry.uses += 1;
+        if leaf {
+            self.leaves.insert(self.key(index));
+        }
+    }
+
+    fn evict(&mut self, parent: u32) -> Option<u32> {
+        let key = *self.leaves.iter().find(|key| key.2 != parent)?;
+        self.leaves.remove(&key);
+        let victim = key.2;
+        let entry = &self.entries[victim as usize];
+        let edge = (entry.parent, entry.byte);
+        if self.edges.get(&edge) == Some(&victim) {
+            self.edges.remove(&edge);
+        }
+        self.entries[edge.0 as usize].children -= 1;
+        if self.is_leaf(edge.0) {
+            self.leaves.insert(self.key(edge.0));
+        }
+        return Some(victim);
+    }
+
+    fn add(&mut self, parent: u32, byte: u8, pos: usize, length: usize) {
+        let entry = Entry {
+            parent,
+            byte,
+            pos,
+            length,
+            children: 0,
+            last_use: self.clock,
+            uses: 1,
+        };
+        let index = if !self.full() {
+            self.entries.push(entry);
+            self.entries.len() as u32 - 1
+        } else if self.policy.evicts() {
+            match self.evict(parent) {
+                Some(victim) => {
+                    self.entries[victim as usize] = entry;
+                    victim
                 }
+                None => return,
             }
-            if !found {
-                break;
+        } else {
+            return;
+        };
+
+        if self.is_leaf(parent) {
+            self.leaves.remove(&self.key(parent));
+        }
+        self.entries[parent as usize].children += 1;
+        self.edges.insert((parent, byte), index);
+        if self.is_leaf(index) {
+            self.leaves.insert(self.key(index));
+        }
+    }
+}
+
+struct TokenWriter {
+    coder: Coder,
+    bits: BitWriter,
+    encoder: Encoder,
+    model: Model,
+}
+
+impl TokenWriter {
+    fn new(coder: Coder) -> Self {
+        return Self {
+            coder,
+            bits: BitWriter::new(),
+            encoder: Encoder::new(),
+            model: Model::new(INDEX_CONTEXTS + 2 * 256),
+        };
+    }
+
+    fn write_index(&mut self, value: u32, width: u32) {
+        match self.coder {
+            Coder::Raw => self.bits.write(value, width),
+            Coder::Adaptive => {
+                let high = std::cmp::min(width, HIGH_BITS);
+                let low = width - high;
+                let base = width as usize * 256;
+                self.encoder
+                    .encode_tree(&mut self.model, base, high, value >> low);
+                self.encoder.encode_direct(value, low);
             }
         }
-        if let Some((_, l, val)) = saved {
-            let t = (val, data[ptr + l]);
-            encoded.push(t);
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, l + 1, count));
-                count += 1;
+    }
+
+    fn write_byte(&mut self, value: u32, byte: u8) {
+        match self.coder {
+            Coder::Raw => self.bits.write(byte as u32, 8),
+            Coder::Adaptive => {
+                let base = INDEX_CONTEXTS + (value == 0) as usize * 256;
+                self.encoder
+                    .encode_tree(&mut self.model, base, 8, byte as u32);
             }
-            ptr += l + 1;
-        } else {
-            encoded.push((0, data[ptr]));
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, 1, count));
-                count += 1;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        match self.coder {
+            Coder::Raw => self.bits.finish(),
+            Coder::Adaptive => {
+                self.encoder.finish();
+                self.encoder.result
             }
-            ptr += 1;
         }
     }
+}
 
-    return encoded;
+struct TokenReader<'a> {
+    coder: Coder,
+    bits: BitReader<'a>,
+    decoder: Decoder<'a>,
+    model: Model,
 }
 
-pub fn compress(data: &Vec<u8>) -> Vec<u8> {
-    let mut result = Vec::new();
-    let encoded = lz78_encode(data);
+impl<'a> TokenReader<'a> {
+    fn new(data: &'a [u8], coder: Coder) -> Self {
+        return Self {
+            coder,
+            bits: BitReader::new(data),
+            decoder: Decoder::new(data),
+            model: Model::new(INDEX_CONTEXTS + 2 * 256),
+        };
+    }
 
-    for (value, byte) in &encoded {
-        let value_bytes: [u8; 4] = unsafe { std::mem::transmute(*value) };
-        result.push(value_bytes[0]);
-        result.push(value_bytes[1]);
-        result.push(*byte);
+    fn read_index(&mut self, width: u32) -> u32 {
+        match self.coder {
+            Coder::Raw => self.bits.read(width),
+            Coder::Adaptive => {
+                let high = std::cmp::min(width, HIGH_BITS);
+                let low = width - high;
+                let base = width as usize * 256;
+                let value = self.decoder.decode_tree(&mut self.model, base, high);
+                (value << low) | self.decoder.decode_direct(low)
+            }
+        }
     }
 
-    return result;
+    fn read_byte(&mut self, value: u32) -> u8 {
+        match self.coder {
+            Coder::Raw => self.bits.read(8) as u8,
+            Coder::Adaptive => {
+                let base = INDEX_CONTEXTS + (value == 0) as usize * 256;
+                self.decoder.decode_tree(&mut self.model, base, 8) as u8
+            }
+        }
+    }
 }
 
-pub fn decompress(data: &Vec<u8>) -> Vec<u8> {
-    let mut decoded = Vec::new();
-    let mut dict = Vec::new();
-    let mut count = 1;
-    let mut ptr = 0;
+// Encodes data[start..] with the dictionary primed by data[..start] and
+// returns the dictionary as it stands at the end.
+fn lz78_encode(
+    data: &Vec<u8>,
+    start: usize,
+    params: &Params,
+    writer: &mut TokenWriter,
+) -> Dictionary {
+    let mut dict = Dictionary::new(params);
+
+    // Like Unix compress, the adaptive policy keeps a full dictionary while
+    // the ratio since the last reset keeps improving between checkpoints.
+    let mut checkpoint = 0;
+    let mut best_ratio = 0.0;
+    let mut reset_ptr = 0;
+    let mut bits = 0;
 
-    let mut caret = 0;
-    while caret < data.len() {
-        let value_bytes: [u8; 4] = [data[caret + 0], data[caret + 1], 0, 0];
-        let value: u32 = unsafe { std::mem::transmute(value_bytes) };
-        let byte = data[caret + 2];
-        caret += 3;
-
-        if value == 0 {
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, 1, count));
-                count += 1;
+    let mut ptr = 0;
+    while ptr < data.len() {
+        if params.policy == Policy::Adaptive && dict.full() && ptr >= checkpoint {
+            checkpoint = ptr + params.check_interval as usize;
+            let ratio = (ptr - reset_ptr) as f64 / bits as f64;
+            if ratio > best_ratio {
+                best_ratio = ratio;
+            } else {
+                if ptr >= start {
+                    writer.write_index(dict.clear_code(), dict.width());
+                }
+                dict.reset();
+                best_ratio = 0.0;
+                reset_ptr = ptr;
+                bits = 0;
             }
-            decoded.push(byte);
-            ptr += 1;
-        } else {
-            let (pos1, l, _) = dict[value as usize - 1];
-            for i in 0..l {
-                decoded.push(decoded[pos1 + i]);
+        }
+
+        let end = if ptr < start { start } else { data.len() };
+        let mut node = 0;
+        let mut l = 0;
+        while ptr + l + 1 < end && l + 1 < 256 {
+            match dict.child(node, data[ptr + l]) {
+                Some(child) => {
+                    node = child;
+                    l += 1;
+                }
+                None => break,
             }
-            decoded.push(byte);
-            if dict.len() < DICT_SIZE {
-                dict.push((ptr, l + 1, count));
-                count += 1;
+        }
+
+        let byte = data[ptr + l];
+        let width = dict.width();
+        if ptr >= start {
+            writer.write_index(node, width);
+            writer.write_byte(node, byte);
+        }
+        bits += width + 8;
+        dict.touch(node);
+        dict.add(node, byte, ptr, l + 1);
+
+        if params.policy == Policy::Reset && dict.full() {
+            if ptr >= start {
+                writer.write_index(dict.clear_code(), dict.width());
             }
-            ptr += l + 1;
+            dict.reset();
+        }
+        ptr += l + 1;
+    }
+
+    return dict;
+}
+
+pub fn compress(data: &Vec<u8>, dict: &Vec<u8>, params: &Params) -> Vec<u8> {
+    let id = if dict.is_empty() { 0 } else { adler32(dict) };
+    let mut result = id.to_le_bytes().to_vec();
+    result.append(&mut params.dump());
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    let input = [dict.as_slice(), data].concat();
+    let mut writer = TokenWriter::new(params.coder);
+    lz78_encode(&input, dict.len(), params, &mut writer);
+    result.append(&mut writer.finish());
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>, dict: &Vec<u8>) -> Vec<u8> {
+    let id = u32::from_le_bytes([archive[0], archive[1], archive[2], archive[3]]);
+    let params = Params::load(&archive[4..]);
+    let caret = 4 + Params::size();
+    let length = u32::from_le_bytes([
+        archive[caret],
+        archive[caret + 1],
+        archive[caret + 2],
+        archive[caret + 3],
+    ]) as usize;
+    let dict = if id == 0 {
+        &[]
+    } else {
+        assert!(
+            !dict.is_empty(),
+            "archive needs a preset dictionary, pass --dict"
+        );
+        assert!(
+            id == adler32(dict),
+            "preset dictionary does not match the archive"
+        );
+        dict.as_slice()
+    };
+
+    // Encoding the preset dictionary leaves the phrases exactly as the
+    // compressor had them, with positions pointing into the decoded prefix.
+    let mut phrases = lz78_encode(
+        &dict.to_vec(),
+        dict.len(),
+        &params,
+        &mut TokenWriter::new(params.coder),
+    );
+    let mut decoded = dict.to_vec();
+    let mut reader = TokenReader::new(&archive[caret + 4..], params.coder);
+    while decoded.len() < dict.len() + length {
+        let value = reader.read_index(phrases.width());
+        if params.policy.clears() && value == phrases.clear_code() {
+            phrases.reset();
+            continue;
+        }
+        assert!(
+            (value as usize) < phrases.entries.len(),
+            "phrase index is not in the dictionary"
+        );
+        let byte = reader.read_byte(value);
+
+        let ptr = decoded.len();
+        let entry = &phrases.entries[value as usize];
+        let (pos1, l) = (entry.pos, entry.length);
+        for i in 0..l {
+            decoded.push(decoded[pos1 + i]);
         }
+        decoded.push(byte);
+        phrases.touch(value);
+        phrases.add(value, byte, ptr, l + 1);
     }
 
-    return decoded;
+    return decoded.split_off(dict.len());
 }
@@ -17,13 +17,37 @@ struct Cli {
 
     #[arg(long, default_value_t = false)]
     decompress: bool,
+
+    #[arg(long)]
+    dict: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = lz78::Policy::Freeze)]
+    policy: lz78::Policy,
+
+    #[arg(long, default_value_t = 10000)]
+    check_interval: u32,
+
+    #[arg(long, default_value_t = 4096)]
+    dict_size: u32,
+
+    #[arg(long, value_enum, default_value_t = lz78::Coder::Raw)]
+    coder: lz78::Coder,
+}
+
+fn read_dict(cli: &Cli) -> Result<Vec<u8>, Error> {
+    let mut dict = Vec::new();
+    if let Some(path) = &cli.dict {
+        let mut dict_f = File::open(path.to_str().unwrap())?;
+        dict_f.read_to_end(&mut dict)?;
+    }
+    return Ok(dict);
 }
 
 fn run_decompressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut archive = Vec::new();
     input_f.read_to_end(&mut archive)?;
-    let data = lz78::decompress(&archive);
+    let data = lz78::decompress(&archive, &read_dict(cli)?);
 
     let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
     output_f.write_all(&data)?;
@@ -35,7 +59,13 @@ fn run_compressor(cli: &Cli) -> Result<(), Error> {
     let mut input_f = File::open(cli.input_file.to_str().unwrap())?;
     let mut data = Vec::new();
     input_f.read_to_end(&mut data)?;
-    let archive = lz78::compress(&data);
+    let params = lz78::Params {
+        policy: cli.policy,
+        check_interval: cli.check_interval,
+        dict_size: cli.dict_size,
+        coder: cli.coder,
+    };
+    let archive = lz78::compress(&data, &read_dict(cli)?, &params);
 
     let mut output_f = File::create(cli.output_file.to_str().unwrap())?;
     output_f.write_all(&archive)?;
@@ -0,0 +1,89 @@
+import argparse
+import os
+import subprocess as sb
+from time import time
+
+# Course test corpus driver. `python3 ctc.py bench [codec ...]` round-trips
+# the texts through every registered codec (or the ones named) and prints
+# ratio and speed tables. Codecs are looked up in their release builds.
+
+codecs = {
+    "huffman": "../lab1-rs/target/release/lab1-rs",
+    "fano": "../lab2/target/release/lab2",
+    "shannon": "../lab3/target/release/lab3",
+    "bwt": "../lab5/target/release/lab5",
+    "gilbert-moore": "../lab6/target/release/lab6",
+    "lz77": "../lab7/target/release/lab7",
+    "lz78": "../lab8/target/release/lab8",
+    "ppm": "../ppm/target/release/ppm",
+    "cm": "../cm/target/release/cm",
+    "dmc": "../dmc/target/release/dmc",
+}
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+def bench(names):
+    missing = [name for name in names if not os.path.exists(codecs[name])]
+    assert not missing, f"build these codecs first: {', '.join(missing)}"
+
+    ratio = {k: {v: 0 for v in names} for k in tests}
+    speed_comp = {k: {v: 0 for v in names} for k in tests}
+    speed_decomp = {k: {v: 0 for v in names} for k in tests}
+
+    for name in names:
+        for test in tests:
+            print(f"Testing {name} {test}")
+            t1 = time()
+            sb.run([codecs[name], "-i", f"../texts/{test}", "-o", "test.ctc", "--compress"],
+                   check=True, capture_output=True)
+            t2 = time()
+            sb.run([codecs[name], "-i", "test.ctc", "-o", "test.out", "--decompress"],
+                   check=True, capture_output=True)
+            t3 = time()
+            assert same(f"../texts/{test}", "test.out"), f"{name} {test}: round-trip failed"
+
+            size = filesize(f"../texts/{test}")
+            arch_size = filesize("test.ctc")
+            ratio[test][name] = size / arch_size
+            speed_comp[test][name] = size / (t2 - t1) / (2 ** 10)
+            speed_decomp[test][name] = arch_size / (t3 - t2) / (2 ** 10)
+
+    sb.run(["rm", "test.ctc", "test.out"])
+
+    for title, table, digits in [
+        ("compress", ratio, 5),
+        ("speed_comp, KiB/s", speed_comp, 3),
+        ("speed_decomp, KiB/s", speed_decomp, 3),
+    ]:
+        print()
+        print(f"{title}: " + " | ".join(names))
+        for test in tests:
+            size = filesize(f"../texts/{test}")
+            print(f"{test}({size}) & ", end="")
+            for name in names:
+                print(f"{round(table[test][name], digits)} & ", end="")
+            print()
+
+parser = argparse.ArgumentParser(prog="ctc")
+commands = parser.add_subparsers(dest="command", required=True)
+bench_parser = commands.add_parser("bench", help="compare codecs on the text corpus")
+bench_parser.add_argument("codecs", nargs="*", help=", ".join(codecs))
+
+args = parser.parse_args()
+if args.command == "bench":
+    unknown = [name for name in args.codecs if name not in codecs]
+    if unknown:
+        parser.error(f"unknown codecs: {', '.join(unknown)}")
+    bench(args.codecs or list(codecs))
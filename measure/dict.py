@@ -0,0 +1,51 @@
+import subprocess as sb
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test2.txt", "test3.txt", "test4.txt", "test5.txt", "test6.txt",
+    "test9.txt", "test10.txt"
+]
+
+codecs = [
+    "./lab7 --method lz77", "./lab7 --method lzss", "./lab7 --method lzh",
+    "./lab7 --method zlib", "./lab7 --method lz4", "./lab8",
+]
+
+with open("../texts/test8.txt", "rb") as f, open("test.dict", "wb") as g:
+    g.write(f.read()[:4096])
+
+result_size = {k: {v: (0, 0) for v in codecs} for k in tests}
+
+for codec in codecs:
+    for test in tests:
+        print(f"Testing {codec} --dict test.dict {test}")
+        run(f"{codec} -i ../texts/{test} -o test.gs --compress")
+        plain = filesize("test.gs")
+        run(f"{codec} -i ../texts/{test} -o test.gs --compress --dict test.dict")
+        run(f"{codec} -i test.gs -o test.out --decompress --dict test.dict")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: round-trip failed"
+        result_size[test][codec] = (plain, filesize("test.gs"))
+
+print()
+print("size without / with dictionary")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for codec in codecs:
+        plain, with_dict = result_size[test][codec]
+        print(f"{plain}/{with_dict} & ", end="")
+    print()
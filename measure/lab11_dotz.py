@@ -0,0 +1,57 @@
+import shutil
+import subprocess as sb
+
+# Writes .Z files with ./lab11 --dot-z and reads them back with lab11 itself
+# and with whichever of gzip -d and uncompress is installed.
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+widths = [9, 12, 16]
+readers = [tool for tool in ["gzip", "uncompress"] if shutil.which(tool)]
+
+shutil.copy("../lab11/target/release/lab11", "lab11")
+
+result_size = {k: {v: 0 for v in widths} for k in tests}
+
+for width in widths:
+    for test in tests:
+        print(f"Testing ./lab11 --dot-z --max-code-bits {width} {test}")
+        run(f"./lab11 -i ../texts/{test} -o test.Z --compress --dot-z --max-code-bits {width}")
+        run(f"./lab11 -i test.Z -o test.out --decompress")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: round-trip failed"
+        for tool in readers:
+            with open("test.Z", "rb") as f, open("test.out", "wb") as out:
+                flag = "-dc" if tool == "gzip" else "-c"
+                sb.run([tool, flag], stdin=f, stdout=out, check=True)
+            assert same(f"../texts/{test}", "test.out"), f"{test}: {tool} read other data"
+        result_size[test][width] = filesize("test.Z")
+
+sb.run(["rm", "lab11", "test.Z", "test.out"])
+
+print()
+print("checked with: " + ", ".join(["lab11"] + readers))
+print("size: " + " | ".join(f"{width} bits" for width in widths))
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for width in widths:
+        print(f"{result_size[test][width]} & ", end="")
+    print()
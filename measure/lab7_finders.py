@@ -0,0 +1,58 @@
+import subprocess as sb
+from time import time
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+finders = [
+    "--finder brute",
+    "--finder hash-chain --chain-depth 16",
+    "--finder hash-chain --chain-depth 256",
+    "--finder hash-chain --chain-depth 256 --nice-length 32",
+]
+
+result_compress = {k: {v: 0 for v in finders} for k in tests}
+result_speed_comp = {k: {v: 0 for v in finders} for k in tests}
+
+for finder in finders:
+    for test in tests:
+        print(f"Testing ./lab7 {finder} {test}")
+        t1 = time()
+        run(f"./lab7 -i ../texts/{test} -o test.gs --compress {finder}")
+        t2 = time()
+
+        size = filesize(f"../texts/{test}")
+        arch_size = filesize("test.gs")
+
+        result_speed_comp[test][finder] = size / (t2 - t1) / (2 ** 10)
+        result_compress[test][finder] = size / arch_size
+
+print()
+print("compress")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for finder in finders:
+        print(f"{round(result_compress[test][finder], 5)} & ", end="")
+    print()
+
+print("speed_comp")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for finder in finders:
+        print(f"{round(result_speed_comp[test][finder], 6)} & ", end="")
+    print()
@@ -0,0 +1,56 @@
+import subprocess as sb
+import zlib
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+parsers = ["greedy", "lazy2", "optimal"]
+
+result_size = {k: {v: 0 for v in parsers + ["gzip -6", "gzip -9"]} for k in tests}
+
+for test in tests:
+    for parser in parsers:
+        print(f"Testing ./lab7 --method gzip --parser {parser} {test} | gzip -d")
+        run(f"./lab7 -i ../texts/{test} -o test.gz --compress --method gzip --parser {parser}")
+        with open("test.out", "wb") as f:
+            sb.run(["gzip", "-d", "-c", "test.gz"], stdout=f, check=True)
+        assert same(f"../texts/{test}", "test.out"), f"{test}: gzip -d rejected our {parser} output"
+        result_size[test][parser] = filesize("test.gz")
+
+        run(f"./lab7 -i ../texts/{test} -o test.z --compress --method zlib --parser {parser}")
+        with open("test.z", "rb") as f, open(f"../texts/{test}", "rb") as g:
+            assert zlib.decompress(f.read()) == g.read(), f"{test}: zlib rejected our {parser} output"
+
+    for level in ["-6", "-9"]:
+        print(f"Testing gzip {level} {test} | ./lab7 --decompress")
+        with open("test.gz", "wb") as f:
+            sb.run(["gzip", level, "-c", f"../texts/{test}"], stdout=f, check=True)
+        run(f"./lab7 -i test.gz -o test.out --decompress")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: failed to read gzip {level} output"
+        result_size[test][f"gzip {level}"] = filesize("test.gz")
+
+print()
+print("size")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for k in parsers + ["gzip -6", "gzip -9"]:
+        print(f"{result_size[test][k]} & ", end="")
+    print()
@@ -0,0 +1,68 @@
+import shutil
+import subprocess as sb
+from time import time
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+lz4_options = ["-1", "-9", "-BD", "-BX", "--content-size", "--no-frame-crc"]
+
+if shutil.which("lz4") is None:
+    print("lz4 CLI not found, skipping interop tests")
+    lz4_options = []
+
+result_size = {k: {v: 0 for v in ["lab7", "lz4 -1"]} for k in tests}
+result_speed = {k: {v: 0 for v in ["comp", "decomp"]} for k in tests}
+
+for test in tests:
+    print(f"Testing ./lab7 --method lz4-frame {test}")
+    size = filesize(f"../texts/{test}")
+    t1 = time()
+    run(f"./lab7 -i ../texts/{test} -o test.lz4 --compress --method lz4-frame")
+    t2 = time()
+    run(f"./lab7 -i test.lz4 -o test.out --decompress")
+    t3 = time()
+    assert same(f"../texts/{test}", "test.out"), f"{test}: round-trip failed"
+    result_size[test]["lab7"] = filesize("test.lz4")
+    result_speed[test]["comp"] = size / (t2 - t1) / (2 ** 20)
+    result_speed[test]["decomp"] = size / (t3 - t2) / (2 ** 20)
+
+    if lz4_options:
+        run(f"lz4 -d -f -q test.lz4 test.out")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: lz4 -d rejected our frame"
+
+    for option in lz4_options:
+        print(f"Testing lz4 {option} {test} | ./lab7 --decompress")
+        run(f"lz4 -f -q {option} ../texts/{test} test.lz4")
+        run(f"./lab7 -i test.lz4 -o test.out --decompress")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: failed to read lz4 {option} output"
+        if option == "-1":
+            result_size[test]["lz4 -1"] = filesize("test.lz4")
+
+print()
+print("size")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & {result_size[test]['lab7']} & {result_size[test]['lz4 -1']} & ")
+
+print("speed, MiB/s")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & {round(result_speed[test]['comp'], 3)} & {round(result_speed[test]['decomp'], 3)} & ")
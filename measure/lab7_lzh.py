@@ -0,0 +1,49 @@
+import subprocess as sb
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+stages = [
+    "--method lz77", "--method lzss", "--method lzh", "--method lzh --parser optimal",
+]
+
+result_size = {k: {v: 0 for v in stages} for k in tests}
+
+for stage in stages:
+    for test in tests:
+        print(f"Testing ./lab7 {stage} {test}")
+        run(f"./lab7 -i ../texts/{test} -o test.gs --compress {stage}")
+        run(f"./lab7 -i test.gs -o test.out --decompress")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: round-trip failed"
+        result_size[test][stage] = filesize("test.gs")
+
+print()
+print("size")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for stage in stages:
+        print(f"{result_size[test][stage]} & ", end="")
+    print()
+
+for test in tests:
+    if filesize(f"../texts/{test}") > 1024:
+        assert result_size[test]["--method lzh"] < result_size[test]["--method lz77"], \
+            f"{test}: entropy-coded tokens are not smaller than raw triples"
@@ -0,0 +1,52 @@
+import subprocess as sb
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+methods = ["lz77", "lz77 --min-length 3", "lzss", "lzh"]
+parsers = ["greedy", "lazy1", "lazy2", "optimal"]
+
+result_size = {m: {k: {v: 0 for v in parsers} for k in tests} for m in methods}
+
+for method in methods:
+    for parser in parsers:
+        for test in tests:
+            print(f"Testing ./lab7 --method {method} --parser {parser} {test}")
+            run(f"./lab7 -i ../texts/{test} -o test.gs --compress --method {method} --parser {parser}")
+            run(f"./lab7 -i test.gs -o test.out --decompress")
+            assert same(f"../texts/{test}", "test.out"), f"{test}: round-trip failed"
+            result_size[method][test][parser] = filesize("test.gs")
+
+for method in methods:
+    print()
+    print(f"{method} gain over greedy, %")
+    for test in tests:
+        greedy = result_size[method][test]["greedy"]
+        print(f"{test}({greedy}) & ", end="")
+        for parser in parsers[1:]:
+            gain = (greedy - result_size[method][test][parser]) / greedy * 100
+            print(f"{round(gain, 3)} & ", end="")
+        print()
+
+for method in methods:
+    for test in tests:
+        for parser in parsers:
+            assert result_size[method][test]["optimal"] <= result_size[method][test][parser], \
+                f"{method} {test}: optimal parse is larger than {parser}"
@@ -0,0 +1,105 @@
+import hashlib
+import random
+import shutil
+import subprocess as sb
+import sys
+import threading
+from time import time
+
+# Streams a synthetic input larger than 4 GiB through ./lab7 --stream and back
+# without ever storing it: the generator feeds the compressor, the compressor
+# feeds the decompressor, and only hashes of both ends are kept.
+
+total = int(sys.argv[1]) if len(sys.argv) > 1 else 5 * 2 ** 30
+chunk_size = 2 ** 20
+window_size = 65536
+
+words = [
+    "".join(random.Random(i).choices("abcdefghijklmnopqrstuvwxyz", k=1 + i % 9))
+    for i in range(4096)
+]
+
+def generate(seed):
+    rng = random.Random(seed)
+    produced = 0
+    while produced < total:
+        chunk = (" ".join(rng.choices(words, k=chunk_size // 5)) + "\n").encode()
+        chunk = chunk[:total - produced]
+        produced += len(chunk)
+        yield chunk
+
+def feed(pipe, digest):
+    for chunk in generate(7):
+        digest.update(chunk)
+        pipe.write(chunk)
+    pipe.close()
+
+shutil.copy("../lab7/target/release/lab7", "lab7")
+
+compressor = sb.Popen(
+    f"./lab7 -i /dev/stdin -o /dev/stdout --compress --stream --window-size {window_size}".split(),
+    stdin=sb.PIPE, stdout=sb.PIPE
+)
+decompressor = sb.Popen(
+    "./lab7 -i /dev/stdin -o /dev/stdout --decompress --stream".split(),
+    stdin=sb.PIPE, stdout=sb.PIPE
+)
+
+archive_size = [0]
+
+def peak_memory(process):
+    try:
+        with open(f"/proc/{process.pid}/status") as f:
+            for line in f:
+                if line.startswith("VmHWM:"):
+                    return int(line.split()[1]) * 1024
+    except FileNotFoundError:
+        pass
+    return 0
+
+def relay():
+    while True:
+        chunk = compressor.stdout.read(chunk_size)
+        if not chunk:
+            break
+        archive_size[0] += len(chunk)
+        decompressor.stdin.write(chunk)
+    decompressor.stdin.close()
+
+source = hashlib.sha256()
+result = hashlib.sha256()
+print(f"Streaming {total} bytes through ./lab7 --stream --window-size {window_size}")
+t1 = time()
+threads = [
+    threading.Thread(target=feed, args=(compressor.stdin, source)),
+    threading.Thread(target=relay),
+]
+for thread in threads:
+    thread.start()
+
+restored = 0
+max_rss = 0
+while True:
+    chunk = decompressor.stdout.read(chunk_size)
+    if not chunk:
+        break
+    restored += len(chunk)
+    result.update(chunk)
+    max_rss = max(max_rss, peak_memory(compressor), peak_memory(decompressor))
+
+for thread in threads:
+    thread.join()
+assert compressor.wait() == 0 and decompressor.wait() == 0, "lab7 failed"
+t2 = time()
+
+sb.run(["rm", "lab7"])
+
+assert restored == total, f"restored {restored} of {total} bytes"
+assert source.digest() == result.digest(), "round-trip mismatch"
+assert max_rss < 16 * 2 ** 20, f"lab7 used {max_rss} bytes of memory"
+
+print()
+print(f"input & {total} & ")
+print(f"archive & {archive_size[0]} & ")
+print(f"max RSS & {max_rss} & ")
+print(f"speed, MiB/s & {round(total / (t2 - t1) / 2 ** 20, 3)} & ")
@@ -0,0 +1,65 @@
+import shutil
+import subprocess as sb
+from time import time
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+    "test1.txt", "test2.txt", "test3.txt", "test4.txt", "test5.txt",
+    "test6.txt", "test7.txt", "test8.txt", "test9.txt", "test10.txt"
+]
+
+policies = [
+    "--policy freeze", "--policy reset",
+    "--policy adaptive --check-interval 1000", "--policy adaptive --check-interval 10000",
+    "--policy lru", "--policy lfu",
+]
+
+shutil.copy("../lab8/target/release/lab8", "lab8")
+
+result_size = {k: {v: 0 for v in policies} for k in tests}
+result_speed = {k: {v: 0 for v in policies} for k in tests}
+
+for policy in policies:
+    for test in tests:
+        print(f"Testing ./lab8 {policy} {test}")
+        size = filesize(f"../texts/{test}")
+        t1 = time()
+        run(f"./lab8 -i ../texts/{test} -o test.lz78 --compress {policy}")
+        t2 = time()
+        run(f"./lab8 -i test.lz78 -o test.out --decompress")
+        assert same(f"../texts/{test}", "test.out"), f"{test}: round-trip failed"
+        result_size[test][policy] = filesize("test.lz78")
+        result_speed[test][policy] = size / (t2 - t1) / (2 ** 20)
+
+sb.run(["rm", "lab8", "test.lz78", "test.out"])
+
+print()
+print("size: " + " | ".join(policies))
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for policy in policies:
+        print(f"{result_size[test][policy]} & ", end="")
+    print()
+
+print("compression speed, MiB/s")
+for test in tests:
+    size = filesize(f"../texts/{test}")
+    print(f"{test}({size}) & ", end="")
+    for policy in policies:
+        print(f"{round(result_speed[test][policy], 3)} & ", end="")
+    print()
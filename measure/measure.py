@@ -19,7 +19,7 @@ tests = [
 
 labs = [
     "./lab1", "./lab2", "./lab3", "python3 lab4.py",
-    "./lab5", "./lab6", "./lab7", "./lab8",
+    "./lab5", "./lab6", "./lab7", "./lab8", "./ppm", "./cm", "./dmc",
 ]
 
 result_compress = {k: {v: 0 for v in labs} for k in tests}
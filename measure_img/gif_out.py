@@ -0,0 +1,89 @@
+import shutil
+import struct
+import subprocess as sb
+
+# Exports palette images as GIF with ./lab11 --gif and checks them against
+# the pixels the tiff crate decodes. The compressor reads GIF input through
+# the gif crate, so the files are decoded independently of our own LZW.
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def read_tiff(path):
+    # Only the uncompressed little-endian files the tiff crate writes.
+    with open(path, "rb") as f:
+        data = f.read()
+    assert data[:4] == b"II*\0"
+    ifd = struct.unpack_from("<I", data, 4)[0]
+    tags = {}
+    for i in range(struct.unpack_from("<H", data, ifd)[0]):
+        tag, kind, count, value = struct.unpack_from("<HHII", data, ifd + 2 + i * 12)
+        size = {3: 2, 4: 4}.get(kind, 1)
+        if kind == 3 and count <= 2:
+            values = struct.unpack_from(f"<{count}H", data, ifd + 10 + i * 12)
+        elif size * count <= 4:
+            values = [value]
+        else:
+            values = struct.unpack_from(f"<{count}{'H' if kind == 3 else 'I'}", data, value)
+        tags[tag] = list(values)
+    assert tags.get(259, [1]) == [1], "compressed TIFF"
+    pixels = b"".join(data[o:o + n] for o, n in zip(tags[273], tags[279]))
+    return tags[256][0], tags[257][0], tags.get(277, [1])[0], pixels
+
+def write_tiff(path, width, height, pixels):
+    bits = struct.pack("<3H", 8, 8, 8)
+    entries = [(256, 4, 1, width), (257, 4, 1, height), (258, 3, 3, 8 + len(pixels)),
+               (259, 3, 1, 1), (262, 3, 1, 2), (273, 4, 1, 8),
+               (277, 3, 1, 3), (278, 4, 1, height), (279, 4, 1, len(pixels))]
+    ifd = struct.pack("<H", len(entries))
+    for entry in entries:
+        ifd += struct.pack("<HHII", *entry)
+    ifd += struct.pack("<I", 0)
+    with open(path, "wb") as f:
+        f.write(b"II*\0" + struct.pack("<I", 14 + len(pixels)) + pixels + bits + ifd)
+
+shutil.copy("../lab11/target/release/lab11", "lab11")
+
+x = range(256)
+write_tiff("rgb.tiff", 256, 256, bytes(
+    v for y in x for i in x for v in (i // 32 * 32, y // 32 * 32, (i ^ y) % 4 * 64)))
+
+tests = [
+"5.1.09.tiff",
+"5.1.11.tiff",
+"5.1.13.tiff",
+"5.1.14.tiff",
+"5.2.10.tiff",
+"5.3.01.tiff",
+"5.3.02.tiff",
+"boat.512.tiff",
+"gray21.512.tiff",
+"ruler.512.tiff",
+"../measure_img/rgb.tiff",
+]
+
+sizes = {}
+for test in tests:
+    print(f"Testing ./lab11 --gif {test}")
+    sb.run(f"./lab11 -i ../images/{test} -o test.arc --compress".split(), check=True)
+    sb.run("./lab11 -i test.arc -o test.tiff --decompress".split(), check=True)
+    sb.run(f"./lab11 -i ../images/{test} -o test.gif --compress --gif".split(), check=True)
+    sb.run("./lab11 -i test.gif -o test2.arc --compress".split(), check=True)
+    sb.run("./lab11 -i test2.arc -o test2.tiff --decompress".split(), check=True)
+    width, height, samples, pixels = read_tiff("test.tiff")
+    gif_width, gif_height, gif_samples, colors = read_tiff("test2.tiff")
+    assert (width, height) == (gif_width, gif_height), f"{test}: wrong size"
+    assert gif_samples == 3
+    if samples == 1:
+        pixels = bytes(p for p in pixels for _ in range(3))
+    assert colors == pixels, f"{test}: wrong pixels"
+    sizes[test] = (filesize(f"../images/{test}"), filesize("test.arc"), filesize("test.gif"))
+
+sb.run(["rm", "lab11", "test.arc", "test.tiff", "test.gif", "test2.arc", "test2.tiff", "rgb.tiff"])
+
+print()
+print("size: TIFF & lab11 private & GIF")
+for test in tests:
+    print(f"{test} & {' & '.join(map(str, sizes[test]))} & ")
@@ -0,0 +1,120 @@
+import random
+import shutil
+import struct
+import subprocess as sb
+
+# Round-trips every --scheme/--coder pair of ./lab10 through synthetic
+# images built around the run length limits (127 for the signed scheme, 128
+# for PackBits), single pixels and single rows, then prints sizes for the
+# test images. The tiff crate refuses images without pixels, so empty
+# channels cannot be fed through the command line.
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def read_tiff(path):
+    # Only the uncompressed little-endian files the tiff crate writes.
+    with open(path, "rb") as f:
+        data = f.read()
+    assert data[:4] == b"II*\0"
+    ifd = struct.unpack_from("<I", data, 4)[0]
+    tags = {}
+    for i in range(struct.unpack_from("<H", data, ifd)[0]):
+        tag, kind, count, value = struct.unpack_from("<HHII", data, ifd + 2 + i * 12)
+        size = {3: 2, 4: 4}.get(kind, 1)
+        if kind == 3 and count <= 2:
+            values = struct.unpack_from(f"<{count}H", data, ifd + 10 + i * 12)
+        elif size * count <= 4:
+            values = [value]
+        else:
+            values = struct.unpack_from(f"<{count}{'H' if kind == 3 else 'I'}", data, value)
+        tags[tag] = list(values)
+    assert tags.get(259, [1]) == [1], "compressed TIFF"
+    return b"".join(data[o:o + n] for o, n in zip(tags[273], tags[279]))
+
+def write_gray_tiff(path, width, height, pixels):
+    assert len(pixels) == width * height
+    entries = [(256, 4, 1, width), (257, 4, 1, height), (258, 3, 1, 8),
+               (259, 3, 1, 1), (262, 3, 1, 1), (273, 4, 1, 8),
+               (277, 3, 1, 1), (278, 4, 1, height), (279, 4, 1, len(pixels))]
+    ifd = struct.pack("<H", len(entries))
+    for entry in entries:
+        ifd += struct.pack("<HHII", *entry)
+    ifd += struct.pack("<I", 0)
+    with open(path, "wb") as f:
+        f.write(b"II*\0" + struct.pack("<I", 8 + len(pixels)) + pixels + ifd)
+
+def repeat(length, value):
+    return bytes([value]) * length
+
+def literal(length, seed):
+    rng = random.Random(seed)
+    result = bytearray()
+    while len(result) < length:
+        byte = rng.randrange(256)
+        if not result or result[-1] != byte:
+            result.append(byte)
+    return bytes(result)
+
+lengths = [1, 2, 3, 126, 127, 128, 129, 130, 254, 255, 256, 257, 383, 384, 385]
+width = 1200
+rows = []
+for n in lengths:
+    rows.append(repeat(n, 7) + literal(width - n, n))
+    rows.append(literal(n, n) + repeat(width - n, 9))
+    rows.append(literal(n, n) + repeat(n, 1) + literal(n, n + 1)
+                + repeat(width - 3 * n, 2))
+synthetic = {
+    "1x1": (1, 1, b"\x2a"),
+    "1x2": (1, 2, b"\x2a\x2b"),
+    "2x1 equal": (2, 1, b"\x2a\x2a"),
+    "single row": (width, 1, rows[4]),
+    "boundaries": (width, len(rows), b"".join(rows)),
+    "flat": (width, 300, repeat(width * 300, 0)),
+    "noise": (width, 300, literal(width * 300, 0)),
+}
+
+schemes = ["signed", "packbits", "bits"]
+pairs = [(scheme, coder) for scheme in schemes for coder in ["raw", "adaptive"]]
+
+shutil.copy("../lab10/target/release/lab10", "lab10")
+
+for name, (w, h, pixels) in synthetic.items():
+    write_gray_tiff("test_in.tiff", w, h, pixels)
+    for scheme, coder in pairs:
+        print(f"Testing ./lab10 --scheme {scheme} --coder {coder} {name}")
+        sb.run(f"./lab10 -i test_in.tiff -o test.rle --compress --scheme {scheme} --coder {coder}".split(), check=True)
+        sb.run("./lab10 -i test.rle -o test.tiff --decompress".split(), check=True)
+        assert read_tiff("test.tiff") == pixels, f"{name}: round-trip failed"
+
+tests = [
+"4.1.04.tiff",
+"4.2.03.tiff",
+"5.1.13.tiff",
+"5.3.01.tiff",
+"boat.512.tiff",
+"gray21.512.tiff",
+"house.tiff",
+"ruler.512.tiff",
+]
+
+result_size = {k: {v: 0 for v in pairs} for k in tests}
+
+for scheme, coder in pairs:
+    for test in tests:
+        print(f"Testing ./lab10 --scheme {scheme} --coder {coder} {test}")
+        sb.run(f"./lab10 -i ../images/{test} -o test.rle --compress --scheme {scheme} --coder {coder}".split(), check=True)
+        result_size[test][(scheme, coder)] = filesize("test.rle")
+
+sb.run(["rm", "lab10", "test_in.tiff", "test.rle", "test.tiff"])
+
+print()
+print("size: " + " | ".join(f"{scheme} {coder}" for scheme, coder in pairs))
+for test in tests:
+    size = filesize(f"../images/{test}")
+    print(f"{test}({size}) & ", end="")
+    for pair in pairs:
+        print(f"{result_size[test][pair]} & ", end="")
+    print()
@@ -0,0 +1,69 @@
+import shutil
+import subprocess as sb
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+"4.1.04.tiff",
+"4.1.05.tiff",
+"4.1.06.tiff",
+"4.1.08.tiff",
+"4.2.01.tiff",
+"4.2.03.tiff",
+"4.2.05.tiff",
+"4.2.07.tiff",
+"5.1.09.tiff",
+"5.1.11.tiff",
+"5.1.13.tiff",
+"5.1.14.tiff",
+"5.2.10.tiff",
+"5.3.01.tiff",
+"5.3.02.tiff",
+"boat.512.tiff",
+"gray21.512.tiff",
+"house.tiff",
+"ruler.512.tiff",
+]
+
+methods = [
+    "--method lz78", "--method lzw --max-code-bits 9", "--method lzw --max-code-bits 12",
+    "--method lzw --max-code-bits 16",
+]
+
+shutil.copy("../lab11/target/release/lab11", "lab11")
+
+result_size = {k: {v: 0 for v in methods} for k in tests}
+
+for method in methods:
+    for test in tests:
+        print(f"Testing ./lab11 {method} {test}")
+        run(f"./lab11 -i ../images/{test} -o test.gs --compress {method}")
+        run(f"./lab11 -i test.gs -o test.tiff --decompress")
+        # Pixels match iff the decoded image compresses to the same archive.
+        run(f"./lab11 -i test.tiff -o test2.gs --compress {method}")
+        assert same("test.gs", "test2.gs"), f"{test}: round-trip failed"
+        result_size[test][method] = filesize("test.gs")
+
+sb.run(["rm", "lab11", "test.gs", "test2.gs", "test.tiff"])
+
+print()
+print("size: " + " | ".join(methods))
+for test in tests:
+    size = filesize(f"../images/{test}")
+    print(f"{test}({size}) & ", end="")
+    for method in methods:
+        print(f"{result_size[test][method]} & ", end="")
+    print()
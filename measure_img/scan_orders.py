@@ -0,0 +1,108 @@
+import random
+import shutil
+import struct
+import subprocess as sb
+
+# Round-trips every --scan order of ./lab10 and ./lab11 through odd-sized
+# synthetic images, then prints archive sizes per order for the test images
+# and the order --scan auto picked (the byte after the header fields).
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def read_tiff(path):
+    # Only the uncompressed little-endian files the tiff crate writes.
+    with open(path, "rb") as f:
+        data = f.read()
+    assert data[:4] == b"II*\0"
+    ifd = struct.unpack_from("<I", data, 4)[0]
+    tags = {}
+    for i in range(struct.unpack_from("<H", data, ifd)[0]):
+        tag, kind, count, value = struct.unpack_from("<HHII", data, ifd + 2 + i * 12)
+        size = {3: 2, 4: 4}.get(kind, 1)
+        if kind == 3 and count <= 2:
+            values = struct.unpack_from(f"<{count}H", data, ifd + 10 + i * 12)
+        elif size * count <= 4:
+            values = [value]
+        else:
+            values = struct.unpack_from(f"<{count}{'H' if kind == 3 else 'I'}", data, value)
+        tags[tag] = list(values)
+    assert tags.get(259, [1]) == [1], "compressed TIFF"
+    return b"".join(data[o:o + n] for o, n in zip(tags[273], tags[279]))
+
+def write_gray_tiff(path, width, height, pixels):
+    assert len(pixels) == width * height
+    entries = [(256, 4, 1, width), (257, 4, 1, height), (258, 3, 1, 8),
+               (259, 3, 1, 1), (262, 3, 1, 1), (273, 4, 1, 8),
+               (277, 3, 1, 1), (278, 4, 1, height), (279, 4, 1, len(pixels))]
+    ifd = struct.pack("<H", len(entries))
+    for entry in entries:
+        ifd += struct.pack("<HHII", *entry)
+    ifd += struct.pack("<I", 0)
+    with open(path, "wb") as f:
+        f.write(b"II*\0" + struct.pack("<I", 8 + len(pixels)) + pixels + ifd)
+
+scans = ["rows", "columns", "serpentine", "hilbert", "tiles", "zigzag", "auto"]
+labs = {"lab10": ("--coder adaptive", 11), "lab11": ("", 12)}
+names = ["rows", "columns", "serpentine", "hilbert", "tiles", "zigzag"]
+
+rng = random.Random(1)
+synthetic = {
+    "1x1": (1, 1, b"\x2a"),
+    "3x1": (3, 1, b"\x01\x02\x03"),
+    "1x5": (1, 5, b"\x01\x02\x03\x04\x05"),
+    "37x11": (37, 11, bytes(rng.randrange(4) for _ in range(37 * 11))),
+    "300x17": (300, 17, bytes(x // 7 for y in range(17) for x in range(300))),
+    "32768x4": (32768, 4, bytes(x // 97 % 256 for y in range(4) for x in range(32768))),
+    "3x5000": (3, 5000, bytes(rng.randrange(2) * 200 for _ in range(3 * 5000))),
+}
+
+tests = [
+"4.1.04.tiff",
+"4.2.03.tiff",
+"5.1.13.tiff",
+"5.3.01.tiff",
+"boat.512.tiff",
+"gray21.512.tiff",
+"house.tiff",
+"ruler.512.tiff",
+]
+
+for lab in labs:
+    shutil.copy(f"../{lab}/target/release/{lab}", lab)
+
+for lab, (options, _) in labs.items():
+    for name, (w, h, pixels) in synthetic.items():
+        write_gray_tiff("test_in.tiff", w, h, pixels)
+        for scan in scans:
+            print(f"Testing ./{lab} --scan {scan} {name}")
+            sb.run(f"./{lab} -i test_in.tiff -o test.arc --compress --scan {scan} {options}".split(), check=True)
+            sb.run(f"./{lab} -i test.arc -o test.tiff --decompress".split(), check=True)
+            assert read_tiff("test.tiff") == pixels, f"{name}: round-trip failed"
+
+result_size = {k: {(lab, scan): 0 for lab in labs for scan in scans} for k in tests}
+picked = {k: {lab: "" for lab in labs} for k in tests}
+
+for lab, (options, scan_byte) in labs.items():
+    for scan in scans:
+        for test in tests:
+            print(f"Testing ./{lab} --scan {scan} {test}")
+            sb.run(f"./{lab} -i ../images/{test} -o test.arc --compress --scan {scan} {options}".split(), check=True)
+            result_size[test][(lab, scan)] = filesize("test.arc")
+            if scan == "auto":
+                with open("test.arc", "rb") as f:
+                    picked[test][lab] = names[f.read()[scan_byte]]
+
+sb.run(["rm", "lab10", "lab11", "test_in.tiff", "test.arc", "test.tiff"])
+
+for lab, (options, _) in labs.items():
+    print()
+    print(f"size: ./{lab} {options} " + " | ".join(scans))
+    for test in tests:
+        size = filesize(f"../images/{test}")
+        print(f"{test}({size}) & ", end="")
+        for scan in scans:
+            print(f"{result_size[test][(lab, scan)]} & ", end="")
+        print(f"{picked[test][lab]} & ")
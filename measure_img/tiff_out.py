@@ -0,0 +1,70 @@
+import shutil
+import subprocess as sb
+
+def run(command):
+    return sb.run(command.split(), capture_output=True) \
+               .stdout \
+               .decode() \
+               .strip()
+
+def filesize(path):
+    with open(path, "rb") as f:
+        data = f.read()
+    return len(data)
+
+def same(path1, path2):
+    with open(path1, "rb") as f1, open(path2, "rb") as f2:
+        return f1.read() == f2.read()
+
+tests = [
+"4.1.04.tiff",
+"4.1.05.tiff",
+"4.1.06.tiff",
+"4.1.08.tiff",
+"4.2.01.tiff",
+"4.2.03.tiff",
+"4.2.05.tiff",
+"4.2.07.tiff",
+"5.1.09.tiff",
+"5.1.11.tiff",
+"5.1.13.tiff",
+"5.1.14.tiff",
+"5.2.10.tiff",
+"5.3.01.tiff",
+"5.3.02.tiff",
+"boat.512.tiff",
+"gray21.512.tiff",
+"house.tiff",
+"ruler.512.tiff",
+]
+
+labs = ["lab10", "lab11"]
+
+for lab in labs:
+    shutil.copy(f"../{lab}/target/release/{lab}", lab)
+
+result_size = {k: {v: (0, 0) for v in labs} for k in tests}
+
+for lab in labs:
+    for test in tests:
+        print(f"Testing ./{lab} --tiff {test}")
+        run(f"./{lab} -i ../images/{test} -o test.gs --compress")
+        run(f"./{lab} -i ../images/{test} -o test.tiff --compress --tiff")
+        # The compressor reads its input through the tiff crate, so the
+        # written file decodes to the same pixels iff it compresses to the
+        # same private archive.
+        run(f"./{lab} -i test.tiff -o test2.gs --compress")
+        assert same("test.gs", "test2.gs"), f"{test}: tiff crate decoded other pixels"
+        result_size[test][lab] = (filesize("test.gs"), filesize("test.tiff"))
+
+sb.run(["rm", "lab10", "lab11", "test.gs", "test2.gs", "test.tiff"])
+
+print()
+print("size: lab10 private / PackBits TIFF, lab11 private / LZW TIFF")
+for test in tests:
+    size = filesize(f"../images/{test}")
+    print(f"{test}({size}) & ", end="")
+    for lab in labs:
+        private, tiff = result_size[test][lab]
+        print(f"{private}/{tiff} & ", end="")
+    print()
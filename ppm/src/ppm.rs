@@ -0,0 +1,283 @@
+use clap::ValueEnum;
+use rangecoder::{Decoder, Encoder};
+
+const MAX_COUNT: u32 = 1 << 13;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Escape {
+    C,
+    D,
+}
+
+impl Escape {
+    fn from_u8(x: u8) -> Escape {
+        if x == 0 {
+            Escape::C
+        } else {
+            Escape::D
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Escape::C => 0,
+            Escape::D => 1,
+        }
+    }
+
+    fn weight(&self, count: u32) -> u32 {
+        match self {
+            Escape::C => count,
+            Escape::D => 2 * count - 1,
+        }
+    }
+}
+
+struct Edge {
+    symbol: u8,
+    count: u32,
+    child: usize,
+}
+
+struct Node {
+    edges: Vec<Edge>,
+    total: u32,
+}
+
+impl Node {
+    fn new() -> Self {
+        Self {
+            edges: Vec::new(),
+            total: 0,
+        }
+    }
+}
+
+struct Stats {
+    symbols: u32,
+    escape: u32,
+}
+
+impl Stats {
+    fn total(&self) -> u32 {
+        self.symbols + self.escape
+    }
+}
+
+struct Model {
+    nodes: Vec<Node>,
+    order: usize,
+    escape: Escape,
+    contexts: Vec<Option<usize>>,
+    excluded: [bool; 256],
+}
+
+impl Model {
+    fn new(order: usize, escape: Escape) -> Self {
+        let mut contexts = vec![None; order + 1];
+        contexts[0] = Some(0);
+        Self {
+            nodes: vec![Node::new()],
+            order,
+            escape,
+            contexts,
+            excluded: [false; 256],
+        }
+    }
+
+    fn stats(&self, node: usize) -> Stats {
+        let mut stats = Stats {
+            symbols: 0,
+            escape: 0,
+        };
+        for edge in &self.nodes[node].edges {
+            if !self.excluded[edge.symbol as usize] {
+                stats.symbols += self.escape.weight(edge.count);
+                stats.escape += 1;
+            }
+        }
+        return stats;
+    }
+
+    fn exclude(&mut self, node: usize) {
+        for edge in &self.nodes[node].edges {
+            self.excluded[edge.symbol as usize] = true;
+        }
+    }
+
+    fn find(&self, node: usize, symbol: u8) -> Option<(u32, u32)> {
+        let mut cum = 0;
+        for edge in &self.nodes[node].edges {
+            if self.excluded[edge.symbol as usize] {
+                continue;
+            }
+            let freq = self.escape.weight(edge.count);
+            if edge.symbol == symbol {
+                return Some((cum, freq));
+            }
+            cum += freq;
+        }
+        return None;
+    }
+
+    fn find_target(&self, node: usize, target: u32) -> (u8, u32, u32) {
+        let mut cum = 0;
+        for edge in &self.nodes[node].edges {
+            if self.excluded[edge.symbol as usize] {
+                continue;
+            }
+            let freq = self.escape.weight(edge.count);
+            if target < cum + freq {
+                return (edge.symbol, cum, freq);
+            }
+            cum += freq;
+        }
+        panic!("corrupted archive");
+    }
+
+    fn increment(&mut self, node: usize, symbol: u8) {
+        let node = &mut self.nodes[node];
+        match node.edges.iter_mut().find(|e| e.symbol == symbol) {
+            Some(edge) => edge.count += 1,
+            None => node.edges.push(Edge {
+                symbol,
+                count: 1,
+                child: 0,
+            }),
+        }
+        node.total += 1;
+        if node.total > MAX_COUNT {
+            node.total = 0;
+            for edge in &mut node.edges {
+                edge.count = (edge.count + 1) / 2;
+                node.total += edge.count;
+            }
+        }
+    }
+
+    fn child(&mut self, node: usize, symbol: u8) -> usize {
+        let pos = self.nodes[node]
+            .edges
+            .iter()
+            .position(|e| e.symbol == symbol)
+            .unwrap();
+        if self.nodes[node].edges[pos].child == 0 {
+            self.nodes.push(Node::new());
+            self.nodes[node].edges[pos].child = self.nodes.len() - 1;
+        }
+        return self.nodes[node].edges[pos].child;
+    }
+
+    fn update(&mut self, symbol: u8, coded_at: Option<usize>) {
+        let lowest = coded_at.unwrap_or(0);
+        for k in lowest..=self.order {
+            if let Some(node) = self.contexts[k] {
+                self.increment(node, symbol);
+            }
+        }
+
+        let mut contexts = vec![None; self.order + 1];
+        contexts[0] = Some(0);
+        for k in 0..self.order {
+            if let Some(node) = self.contexts[k] {
+                contexts[k + 1] = Some(self.child(node, symbol));
+            }
+        }
+        self.contexts = contexts;
+    }
+
+    fn encode(&mut self, encoder: &mut Encoder, symbol: u8) {
+        self.excluded = [false; 256];
+        let mut coded_at = None;
+        for k in (0..=self.order).rev() {
+            let node = match self.contexts[k] {
+                Some(node) => node,
+                None => continue,
+            };
+            let stats = self.stats(node);
+            if stats.total() == 0 {
+                continue;
+            }
+            if let Some((cum, freq)) = self.find(node, symbol) {
+                encoder.encode_freq(cum, freq, stats.total());
+                coded_at = Some(k);
+                break;
+            }
+            encoder.encode_freq(stats.symbols, stats.escape, stats.total());
+            self.exclude(node);
+        }
+
+        if coded_at.is_none() {
+            let cum = (0..symbol).filter(|&s| !self.excluded[s as usize]).count();
+            let total = self.excluded.iter().filter(|&&e| !e).count();
+            encoder.encode_freq(cum as u32, 1, total as u32);
+        }
+
+        self.update(symbol, coded_at);
+    }
+
+    fn decode(&mut self, decoder: &mut Decoder) -> u8 {
+        self.excluded = [false; 256];
+        for k in (0..=self.order).rev() {
+            let node = match self.contexts[k] {
+                Some(node) => node,
+                None => continue,
+            };
+            let stats = self.stats(node);
+            if stats.total() == 0 {
+                continue;
+            }
+            let target = decoder.get_freq(stats.total());
+            if target < stats.symbols {
+                let (symbol, cum, freq) = self.find_target(node, target);
+                decoder.decode_freq(cum, freq);
+                self.update(symbol, Some(k));
+                return symbol;
+            }
+            decoder.decode_freq(stats.symbols, stats.escape);
+            self.exclude(node);
+        }
+
+        let total = self.excluded.iter().filter(|&&e| !e).count();
+        let target = decoder.get_freq(total as u32);
+        let symbol = (0..=255u8)
+            .filter(|&s| !self.excluded[s as usize])
+            .nth(target as usize)
+            .unwrap();
+        decoder.decode_freq(target, 1);
+        self.update(symbol, None);
+        return symbol;
+    }
+}
+
+pub fn compress(data: &Vec<u8>, order: u8, escape: Escape) -> Vec<u8> {
+    let mut result = Vec::new();
+    result.push(order);
+    result.push(escape.to_u8());
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+    let mut model = Model::new(order as usize, escape);
+    let mut encoder = Encoder::new();
+    for byte in data {
+        model.encode(&mut encoder, *byte);
+    }
+    encoder.finish();
+    result.append(&mut encoder.result);
+
+    return result;
+}
+
+pub fn decompress(archive: &Vec<u8>) -> Vec<u8> {
+    let mut result = Vec::new();
+    let order = archive[0] as usize;
+    let escape = Escape::from_u8(archive[1]);
+    let length = u32::from_le_bytes([archive[2], archive[3], archive[4], archive[5]]) as usize;
+
+    let mut model = Model::new(order, escape);
+    let mut decoder = Decoder::new(&archive[6..]);
+    for _ in 0..length {
+        result.push(model.decode(&mut decoder));
+    }
+
+    return result;
+}
@@ -0,0 +1,222 @@
+pub const PROB_BITS: u32 = 12;
+pub const PROB_ONE: u32 = 1 << PROB_BITS;
+pub const PROB_INIT: u16 = (PROB_ONE / 2) as u16;
+const MOVE_BITS: u32 = 5;
+const TOP: u32 = 1 << 24;
+
+pub struct Model {
+    probs: Vec<u16>,
+}
+
+impl Model {
+    pub fn new(contexts: usize) -> Self {
+        Self {
+            probs: vec![PROB_INIT; contexts],
+        }
+    }
+
+    pub fn get(&mut self, context: usize) -> &mut u16 {
+        return &mut self.probs[context];
+    }
+}
+
+fn update(prob: &mut u16, bit: u32) {
+    if bit == 0 {
+        *prob += ((PROB_ONE - *prob as u32) >> MOVE_BITS) as u16;
+    } else {
+        *prob -= *prob >> MOVE_BITS;
+    }
+}
+
+pub struct Encoder {
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    pub result: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self {
+            low: 0,
+            range: 0xFFFFFFFF,
+            cache: 0,
+            cache_size: 1,
+            result: Vec::new(),
+        }
+    }
+
+    fn shift_low(&mut self) {
+        if (self.low as u32) < 0xFF000000 || (self.low >> 32) != 0 {
+            let carry = (self.low >> 32) as u8;
+            let mut byte = self.cache;
+            while self.cache_size > 0 {
+                self.result.push(byte.wrapping_add(carry));
+                byte = 0xFF;
+                self.cache_size -= 1;
+            }
+            self.cache = (self.low >> 24) as u8;
+        }
+        self.cache_size += 1;
+        self.low = (self.low & 0x00FFFFFF) << 8;
+    }
+
+    fn normalize(&mut self) {
+        while self.range < TOP {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+
+    pub fn encode_with(&mut self, p0: u32, bit: u32) {
+        let p0 = p0.clamp(1, PROB_ONE - 1);
+        let bound = (self.range >> PROB_BITS) * p0;
+        if bit == 0 {
+            self.range = bound;
+        } else {
+            self.low += bound as u64;
+            self.range -= bound;
+        }
+        self.normalize();
+    }
+
+    pub fn encode_bit(&mut self, prob: &mut u16, bit: u32) {
+        self.encode_with(*prob as u32, bit);
+        update(prob, bit);
+    }
+
+    pub fn encode_direct(&mut self, value: u32, count: u32) {
+        for i in (0..count).rev() {
+            self.range >>= 1;
+            if (value >> i) & 1 == 1 {
+                self.low += self.range as u64;
+            }
+            self.normalize();
+        }
+    }
+
+    // Codes a symbol occupying [start, start + size) out of `total` counts,
+    // for models that keep whole frequency tables.
+    pub fn encode_freq(&mut self, start: u32, size: u32, total: u32) {
+        self.range /= total;
+        self.low += start as u64 * self.range as u64;
+        self.range *= size;
+        self.normalize();
+    }
+
+    pub fn encode_tree(&mut self, model: &mut Model, base: usize, bits: u32, value: u32) {
+        let mut node = 1;
+        for i in (0..bits).rev() {
+            let bit = (value >> i) & 1;
+            self.encode_bit(model.get(base + node), bit);
+            node = (node << 1) | bit as usize;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        for _ in 0..5 {
+            self.shift_low();
+        }
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    ptr: usize,
+    code: u32,
+    range: u32,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut decoder = Self {
+            data,
+            ptr: 0,
+            code: 0,
+            range: 0xFFFFFFFF,
+        };
+        for _ in 0..5 {
+            decoder.code = (decoder.code << 8) | decoder.next_byte() as u32;
+        }
+        return decoder;
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.data.get(self.ptr).copied().unwrap_or(0);
+        self.ptr += 1;
+        return byte;
+    }
+
+    fn normalize(&mut self) {
+        while self.range < TOP {
+            self.range <<= 8;
+            self.code = (self.code << 8) | self.next_byte() as u32;
+        }
+    }
+
+    pub fn decode_with(&mut self, p0: u32) -> u32 {
+        let p0 = p0.clamp(1, PROB_ONE - 1);
+        let bound = (self.range >> PROB_BITS) * p0;
+        let bit = if self.code < bound {
+            self.range = bound;
+            0
+        } else {
+            self.code -= bound;
+            self.range -= bound;
+            1
+        };
+        self.normalize();
+        return bit;
+    }
+
+    pub fn decode_bit(&mut self, prob: &mut u16) -> u32 {
+        let bit = self.decode_with(*prob as u32);
+        update(prob, bit);
+        return bit;
+    }
+
+    pub fn decode_direct(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for _ in 0..count {
+            self.range >>= 1;
+            let bit = if self.code >= self.range {
+                self.code -= self.range;
+                1
+            } else {
+                0
+            };
+            value = (value << 1) | bit;
+            self.normalize();
+        }
+        return value;
+    }
+
+    // Returns the count the next symbol falls on, the caller then finds the
+    // symbol and passes its interval to `decode_freq`.
+    pub fn get_freq(&mut self, total: u32) -> u32 {
+        self.range /= total;
+        return std::cmp::min(self.code / self.range, total - 1);
+    }
+
+    pub fn decode_freq(&mut self, start: u32, size: u32) {
+        self.code -= start * self.range;
+        self.range *= size;
+        self.normalize();
+    }
+
+    pub fn decode_tree(&mut self, model: &mut Model, base: usize, bits: u32) -> u32 {
+        let mut node = 1;
+        for _ in 0..bits {
+            let bit = self.decode_bit(model.get(base + node));
+            node = (node << 1) | bit as usize;
+        }
+        return (node - (1 << bits)) as u32;
+    }
+}
@@ -0,0 +1,204 @@
+use clap::ValueEnum;
+
+// The order in which pixels are fed to the coder. Runs and phrases break at
+// the end of every line of the traversal, so vertical structures do better
+// column by column, and the serpentine, Hilbert, tile and zigzag orders keep
+// consecutive pixels next to each other across line breaks.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Scan {
+    Rows,
+    Columns,
+    Serpentine,
+    Hilbert,
+    Tiles,
+    // Anti-diagonals in alternating directions, as JPEG orders coefficients.
+    Zigzag,
+    // Tries every order and keeps the smallest archive.
+    Auto,
+}
+
+const ORDERS: [Scan; 6] = [
+    Scan::Rows,
+    Scan::Columns,
+    Scan::Serpentine,
+    Scan::Hilbert,
+    Scan::Tiles,
+    Scan::Zigzag,
+];
+const TILE_SIZE: usize = 8;
+
+impl Scan {
+    pub fn from_u8(x: u8) -> Scan {
+        match x {
+            0 => Scan::Rows,
+            1 => Scan::Columns,
+            2 => Scan::Serpentine,
+            3 => Scan::Hilbert,
+            4 => Scan::Tiles,
+            _ => Scan::Zigzag,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Scan::Rows => 0,
+            Scan::Columns => 1,
+            Scan::Serpentine => 2,
+            Scan::Hilbert => 3,
+            Scan::Tiles => 4,
+            Scan::Zigzag => 5,
+            Scan::Auto => panic!("auto is not stored, pick an order first"),
+        }
+    }
+}
+
+// Generalized Hilbert ("gilbert") curve, after Jakub Červený's gilbert2d.
+// It fills the rectangle spanned by the major axis `a` and the minor axis
+// `b` from `start`, splitting it into two or three parts with even sides,
+// so the walk stays inside any width and height and only steps between
+// neighbours, apart from one diagonal step when the long side is even and
+// the short one odd. Raster indices are appended to `result`.
+fn gilbert(result: &mut Vec<usize>, width: usize, start: (i64, i64), a: (i64, i64), b: (i64, i64)) {
+    let (x, y) = start;
+    let w = (a.0 + a.1).abs();
+    let h = (b.0 + b.1).abs();
+    let da = (a.0.signum(), a.1.signum());
+    let db = (b.0.signum(), b.1.signum());
+
+    if w == 0 || h == 0 {
+        return;
+    }
+    if h == 1 || w == 1 {
+        let (d, n) = if h == 1 { (da, w) } else { (db, h) };
+        for i in 0..n {
+            result.push(((y + i * d.1) * width as i64 + x + i * d.0) as usize);
+        }
+        return;
+    }
+
+    let mut a2 = (a.0.div_euclid(2), a.1.div_euclid(2));
+    let mut b2 = (b.0.div_euclid(2), b.1.div_euclid(2));
+    if 2 * w > 3 * h {
+        // Long and thin: split along the major axis only.
+        if (a2.0 + a2.1).abs() % 2 == 1 && w > 2 {
+            a2 = (a2.0 + da.0, a2.1 + da.1);
+        }
+        gilbert(result, width, (x, y), a2, b);
+        gilbert(
+            result,
+            width,
+            (x + a2.0, y + a2.1),
+            (a.0 - a2.0, a.1 - a2.1),
+            b,
+        );
+    } else {
+        if (b2.0 + b2.1).abs() % 2 == 1 && h > 2 {
+            b2 = (b2.0 + db.0, b2.1 + db.1);
+        }
+        gilbert(result, width, (x, y), b2, a2);
+        gilbert(
+            result,
+            width,
+            (x + b2.0, y + b2.1),
+            a,
+            (b.0 - b2.0, b.1 - b2.1),
+        );
+        gilbert(
+            result,
+            width,
+            (
+                x + (a.0 - da.0) + (b2.0 - db.0),
+                y + (a.1 - da.1) + (b2.1 - db.1),
+            ),
+            (-b2.0, -b2.1),
+            (-(a.0 - a2.0), -(a.1 - a2.1)),
+        );
+    }
+}
+
+// Pixel indices in raster order, listed in the order of the traversal.
+fn order(scan: Scan, dim: (u32, u32)) -> Vec<usize> {
+    let (width, height) = (dim.0 as usize, dim.1 as usize);
+    let mut result = Vec::with_capacity(width * height);
+
+    match scan {
+        Scan::Rows | Scan::Auto => result.extend(0..width * height),
+        Scan::Columns => {
+            for x in 0..width {
+                result.extend((0..height).map(|y| y * width + x));
+            }
+        }
+        Scan::Serpentine => {
+            for y in 0..height {
+                if y % 2 == 0 {
+                    result.extend((0..width).map(|x| y * width + x));
+                } else {
+                    result.extend((0..width).rev().map(|x| y * width + x));
+                }
+            }
+        }
+        Scan::Hilbert => {
+            let (w, h) = (width as i64, height as i64);
+            if width >= height {
+                gilbert(&mut result, width, (0, 0), (w, 0), (0, h));
+            } else {
+                gilbert(&mut result, width, (0, 0), (0, h), (w, 0));
+            }
+        }
+        Scan::Tiles => {
+            for ty in (0..height).step_by(TILE_SIZE) {
+                for tx in (0..width).step_by(TILE_SIZE) {
+                    for y in ty..std::cmp::min(ty + TILE_SIZE, height) {
+                        for x in tx..std::cmp::min(tx + TILE_SIZE, width) {
+                            result.push(y * width + x);
+                        }
+                    }
+                }
+            }
+        }
+        Scan::Zigzag => {
+            // Diagonal s holds the pixels with x + y == s, walked down-left
+            // when s is odd and up-right when it is even.
+            for s in 0..(width + height).saturating_sub(1) {
+                let diagonal = (s + 1).saturating_sub(width)..std::cmp::min(s + 1, height);
+                if s % 2 == 1 {
+                    result.extend(diagonal.map(|y| y * width + s - y));
+                } else {
+                    result.extend(diagonal.rev().map(|y| y * width + s - y));
+                }
+            }
+        }
+    }
+
+    return result;
+}
+
+pub fn apply(data: &[u8], dim: (u32, u32), samples: usize, scan: Scan) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    for i in order(scan, dim) {
+        result.extend_from_slice(&data[i * samples..(i + 1) * samples]);
+    }
+    return result;
+}
+
+pub fn undo(data: &[u8], dim: (u32, u32), samples: usize, scan: Scan) -> Vec<u8> {
+    let mut result = vec![0; data.len()];
+    for (pos, i) in order(scan, dim).into_iter().enumerate() {
+        result[i * samples..(i + 1) * samples]
+            .copy_from_slice(&data[pos * samples..(pos + 1) * samples]);
+    }
+    return result;
+}
+
+// Runs `compress` with the requested order, or with every order for auto,
+// returning the smallest archive.
+pub fn pick(scan: Scan, compress: impl Fn(Scan) -> Vec<u8>) -> Vec<u8> {
+    if scan != Scan::Auto {
+        return compress(scan);
+    }
+    return ORDERS
+        .iter()
+        .map(|&scan| compress(scan))
+        .min_by_key(|archive| archive.len())
+        .unwrap();
+}
@@ -0,0 +1,87 @@
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const STRIP_SIZE: usize = 8192;
+
+pub fn rows_per_strip(dim: (u32, u32), samples: u32) -> u32 {
+    let row = dim.0 as usize * samples as usize;
+    return (STRIP_SIZE / std::cmp::max(row, 1)).clamp(1, std::cmp::max(dim.1, 1) as usize) as u32;
+}
+
+fn entry(ifd: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32) {
+    ifd.extend_from_slice(&tag.to_le_bytes());
+    ifd.extend_from_slice(&kind.to_le_bytes());
+    ifd.extend_from_slice(&count.to_le_bytes());
+    ifd.extend_from_slice(&value.to_le_bytes());
+}
+
+// Values that do not fit the 4-byte entry field go to the end of the file
+// and the entry points at them.
+fn array(result: &mut Vec<u8>, kind: u16, values: &[u32]) -> u32 {
+    let size = if kind == SHORT { 2 } else { 4 };
+    if values.len() * size <= 4 {
+        let mut inline = Vec::new();
+        for &value in values {
+            inline.extend_from_slice(&value.to_le_bytes()[..size]);
+        }
+        inline.resize(4, 0);
+        return u32::from_le_bytes([inline[0], inline[1], inline[2], inline[3]]);
+    }
+    let offset = result.len() as u32;
+    for &value in values {
+        result.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+    return offset;
+}
+
+pub fn write(
+    dim: (u32, u32),
+    samples: u32,
+    compression: u16,
+    predictor: bool,
+    strips: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut result = b"II".to_vec();
+    result.extend_from_slice(&42u16.to_le_bytes());
+    result.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut offsets = Vec::new();
+    let mut counts = Vec::new();
+    for strip in strips {
+        offsets.push(result.len() as u32);
+        counts.push(strip.len() as u32);
+        result.extend_from_slice(strip);
+        if result.len() % 2 == 1 {
+            result.push(0);
+        }
+    }
+
+    let bits = array(&mut result, SHORT, &vec![8; samples as usize]);
+    let offsets = array(&mut result, LONG, &offsets);
+    let counts = array(&mut result, LONG, &counts);
+    if result.len() % 2 == 1 {
+        result.push(0);
+    }
+
+    let mut ifd = Vec::new();
+    let photometric = if samples == 1 { 1 } else { 2 };
+    entry(&mut ifd, 256, LONG, 1, dim.0);
+    entry(&mut ifd, 257, LONG, 1, dim.1);
+    entry(&mut ifd, 258, SHORT, samples, bits);
+    entry(&mut ifd, 259, SHORT, 1, compression as u32);
+    entry(&mut ifd, 262, SHORT, 1, photometric);
+    entry(&mut ifd, 273, LONG, strips.len() as u32, offsets);
+    entry(&mut ifd, 277, SHORT, 1, samples);
+    entry(&mut ifd, 278, LONG, 1, rows_per_strip(dim, samples));
+    entry(&mut ifd, 279, LONG, strips.len() as u32, counts);
+    entry(&mut ifd, 284, SHORT, 1, 1);
+    if predictor {
+        entry(&mut ifd, 317, SHORT, 1, 2);
+    }
+
+    let ifd_offset = result.len() as u32;
+    result[4..8].copy_from_slice(&ifd_offset.to_le_bytes());
+    result.extend_from_slice(&((ifd.len() / 12) as u16).to_le_bytes());
+    result.append(&mut ifd);
+    result.extend_from_slice(&0u32.to_le_bytes());
+    return result;
+}